@@ -0,0 +1,64 @@
+// counts allocator traffic while scanning generated programs of growing size.
+// run with `cargo bench --bench alloc`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+
+struct Counting;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const LINE: &str = "(\"abc\" + \"def\") * (\"g\" == \"h\"); // note\n";
+
+fn main() {
+    println!(
+        "{:>10} {:>10} {:>12} {:>14}",
+        "lines", "tokens", "allocations", "bytes"
+    );
+    for lines in [1_000, 10_000, 100_000] {
+        let source = LINE.repeat(lines);
+
+        ALLOCS.store(0, Ordering::Relaxed);
+        BYTES.store(0, Ordering::Relaxed);
+        let mut names = Interner::new();
+        let mut scanner = Scanner::new(FileId::default(), source, &mut names);
+        let (tokens, errors) = scanner.scan_tokens();
+        assert!(errors.is_empty(), "bench source should scan");
+        let allocs = ALLOCS.load(Ordering::Relaxed);
+        let bytes = BYTES.load(Ordering::Relaxed);
+
+        println!(
+            "{:>10} {:>10} {:>12} {:>14}",
+            lines,
+            tokens.len(),
+            allocs,
+            bytes
+        );
+    }
+}
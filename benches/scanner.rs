@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+
+fn scan(source: &str) -> usize {
+    let mut names = Interner::new();
+    let mut scanner = Scanner::new(FileId::default(), source.to_string(), &mut names);
+    let (tokens, errors) = scanner.scan_tokens();
+    assert!(errors.is_empty(), "bench source should scan");
+    tokens.len()
+}
+
+// mostly comments and indentation, the way documented library code looks
+fn comment_heavy(lines: usize) -> String {
+    let block = concat!(
+        "// this helper does a thing. it is explained at some length here\n",
+        "// because the comment is most of what is on the line, and the\n",
+        "// scanner has to walk past every one of these characters.\n",
+        "        (\"a long-ish string literal to skip over\" + \"more\");\n",
+        "\n",
+    );
+    block.repeat(lines / 5)
+}
+
+// dense code with every kind of token and little trivia
+fn dense(bytes: usize) -> String {
+    let block = concat!(
+        "fun fib(n) { if (n <= 1) return n; return fib(n - 2) + fib(n - 1); }\n",
+        "var greeting = \"hello ${name}, you are ${age * 2.5} \\\"units\\\"\";\n",
+        "class Point < Base { init(x, y) { this.x = x; this.y = y; } }\n",
+        "while (i != 10 and !done) { print i >= 3 or i == 7; i = i + 1; }\n",
+    );
+    block.repeat(bytes / block.len() + 1)
+}
+
+// throughput should stay flat as the input grows if scanning is linear
+fn scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scale");
+    for kib in [64, 256, 1024] {
+        let source = dense(kib * 1024);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("dense", kib), &source, |b, s| {
+            b.iter(|| scan(s))
+        });
+    }
+    group.finish();
+}
+
+fn skipping(c: &mut Criterion) {
+    let mut group = c.benchmark_group("skip");
+    for lines in [1_000, 10_000] {
+        let source = comment_heavy(lines);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("comment_heavy", lines), &source, |b, s| {
+            b.iter(|| scan(s))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scaling, skipping);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+// what % and the bitwise operators do to numbers. there's no interpreter to
+// run them yet, so they're pinned down here, where it can call them.
+//
+// lox only has doubles. % works on them directly, with the sign of the
+// left operand, as in c's fmod. the bitwise operators first truncate
+// their operands toward zero to an i64, saturating past its range and
+// taking NaN as 0, then work on the bits, and give the result back as a
+// double. past 2^53 that's rounded, like any other number that large
+
+pub fn remainder(a: f64, b: f64) -> f64 {
+    a % b
+}
+
+pub fn bit_and(a: f64, b: f64) -> f64 {
+    (int(a) & int(b)) as f64
+}
+
+pub fn bit_or(a: f64, b: f64) -> f64 {
+    (int(a) | int(b)) as f64
+}
+
+pub fn bit_xor(a: f64, b: f64) -> f64 {
+    (int(a) ^ int(b)) as f64
+}
+
+pub fn bit_not(a: f64) -> f64 {
+    !int(a) as f64
+}
+
+// shift counts are taken mod 64, as java does for longs, so every count is
+// defined and a negative one counts back from 64
+pub fn shift_left(a: f64, count: f64) -> f64 {
+    int(a).wrapping_shl(int(count) as u32) as f64
+}
+
+// arithmetic, so the sign is kept: -8 >> 1 is -4
+pub fn shift_right(a: f64, count: f64) -> f64 {
+    int(a).wrapping_shr(int(count) as u32) as f64
+}
+
+fn int(n: f64) -> i64 {
+    n as i64
+}
@@ -1 +1,4 @@
-pub mod scanner;
\ No newline at end of file
+pub mod scanner;
+pub mod parser;
+pub mod printer;
+pub mod arithmetic;
\ No newline at end of file
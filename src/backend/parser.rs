@@ -0,0 +1,888 @@
+use crate::data::ast::{Expr, ExprKind, Function, Literal, Stmt, StmtKind};
+use crate::data::error::{ParseError, ParseErrorKind};
+use crate::data::limits::Limits;
+use crate::data::options::{Extension, ParserOptions};
+use crate::data::span::Span;
+use crate::data::token::Token;
+use crate::data::types::TokenType;
+
+// recursive descent over the lox grammar, one method per rule:
+//
+//   program     -> declaration* EOF
+//   declaration -> classDecl | funDecl | varDecl | statement
+//   classDecl   -> "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}"
+//   funDecl     -> "fun" function
+//   function    -> IDENTIFIER "(" parameters? ")" block
+//   parameters  -> IDENTIFIER ( "," IDENTIFIER )*
+//   varDecl     -> "var" IDENTIFIER ( "=" expression )? ";"
+//   statement   -> exprStmt | forStmt | ifStmt | printStmt | returnStmt
+//                | whileStmt | block
+//   exprStmt    -> expression ";"
+//   forStmt     -> "for" "(" ( varDecl | exprStmt | ";" )
+//                  expression? ";" expression? ")" statement
+//   ifStmt      -> "if" "(" expression ")" statement ( "else" statement )?
+//   printStmt   -> "print" expression ";"
+//   returnStmt  -> "return" expression? ";"
+//   whileStmt   -> "while" "(" expression ")" statement
+//   block       -> "{" declaration* "}"
+//
+//   expression  -> comma
+//   comma       -> assignment ( "," assignment )*
+//   assignment  -> ( call "." )? IDENTIFIER "=" assignment | conditional
+//   conditional -> logic_or ( "?" expression ":" conditional )?
+//   logic_or    -> logic_and ( "or" logic_and )*
+//   logic_and   -> equality ( "and" equality )*
+//   equality    -> comparison ( ( "!=" | "==" ) comparison )*
+//   comparison  -> bit_or ( ( ">" | ">=" | "<" | "<=" ) bit_or )*
+//   bit_or      -> bit_xor ( "|" bit_xor )*
+//   bit_xor     -> bit_and ( "^" bit_and )*
+//   bit_and     -> shift ( "&" shift )*
+//   shift       -> term ( ( "<<" | ">>" ) term )*
+//   term        -> factor ( ( "-" | "+" ) factor )*
+//   factor      -> unary ( ( "/" | "*" | "%" ) unary )*
+//   unary       -> ( "!" | "-" | "~" ) unary | call
+//   call        -> primary ( "(" arguments? ")" | "." IDENTIFIER )*
+//   arguments   -> assignment ( "," assignment )*
+//   primary     -> NUMBER | STRING | RAW_STRING | interpolation | "true"
+//               | "false" | "nil" | "(" expression ")" | IDENTIFIER
+//               | "this" | "super" "." IDENTIFIER
+pub struct Parser {
+    tokens: Vec<Token>, // always ends in End
+    current: usize,     // index of the next token to consume
+    depth: usize,       // levels of tree above the node being parsed, see nested
+    options: ParserOptions,
+    // errors that didn't stop the parse, plus those recovered from by
+    // skipping to the next statement, in the order they're met
+    errors: Vec<ParseError>,
+}
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+// most arguments a call can pass, and so parameters a function can take
+const MAX_ARITY: usize = 255;
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self::with_limits(tokens, Limits::default())
+    }
+
+    pub fn with_limits(tokens: Vec<Token>, limits: Limits) -> Self {
+        Self::with_options(tokens, ParserOptions::new().limits(limits))
+    }
+
+    pub fn with_options(mut tokens: Vec<Token>, options: ParserOptions) -> Self {
+        // comments are only there for tools that asked the scanner to keep them
+        tokens.retain(|token| !matches!(token.tt(), TokenType::Comment(_)));
+        if !matches!(tokens.last().map(Token::tt), Some(TokenType::End)) {
+            // an empty span just past the last token, where a scan would put it
+            let span = tokens.last().map_or(Span::default(), |last| Span {
+                start: last.span().end,
+                ..last.span()
+            });
+            tokens.push(Token::new(TokenType::End, span));
+        }
+        Self {
+            tokens,
+            current: 0,
+            depth: 0,
+            options,
+            errors: Vec::new(),
+        }
+    }
+
+    // a whole program, along with every syntax error found in it. a
+    // statement with an error is left out, and parsing picks up again at
+    // the next statement
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<ParseError>) {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            statements.extend(self.declaration());
+        }
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    // a single expression spanning the whole input, or the first error in it
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let mut expr = self.expression();
+        if expr.is_ok() && !self.is_at_end() {
+            expr = Err(self.error(ParseErrorKind::ExpectEnd));
+        }
+        match self.errors.drain(..).next() {
+            Some(err) => Err(err),
+            None => expr,
+        }
+    }
+
+    // cursor snapshot for internal error reports
+    pub fn state(&self) -> String {
+        format!(
+            "token {} of {}, at {:?}, depth {}",
+            self.current,
+            self.tokens.len(),
+            self.peek(),
+            self.depth
+        )
+    }
+
+    pub fn line(&self) -> u32 {
+        self.peek().line()
+    }
+
+    // where errors are caught: the rest of the statement is skipped, so
+    // one mistake doesn't set off a cascade of others
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.try_declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    // panic mode: discards tokens up to a likely statement boundary, just
+    // past a semicolon or just before a keyword that starts a statement
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().tt() == &TokenType::Semicolon {
+                return;
+            }
+            match self.peek().tt() {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn try_declaration(&mut self) -> Result<Stmt> {
+        if self.match_any(&[TokenType::Class]) {
+            return self.class_declaration();
+        }
+        if self.match_any(&[TokenType::Fun]) {
+            let start = self.previous().span();
+            let function = self.function(start, ParseErrorKind::ExpectFunctionName)?;
+            let span = function.span;
+            return Ok(Stmt::new(StmtKind::Function(function), span));
+        }
+        if self.match_any(&[TokenType::Var]) {
+            return self.var_declaration();
+        }
+        self.statement()
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt> {
+        let start = self.previous().span();
+        let name = self.identifier(ParseErrorKind::ExpectClassName)?;
+        let mut superclass = None;
+        if self.match_any(&[TokenType::Less]) {
+            let name = self.identifier(ParseErrorKind::ExpectSuperclassName)?;
+            let span = name.span();
+            superclass = Some(Expr::new(ExprKind::Variable(name), span));
+        }
+        if !self.check(&TokenType::LeftBrace) {
+            return Err(self.error(ParseErrorKind::ExpectLeftBraceBeforeClassBody));
+        }
+        let methods = self.nested(Self::class_body)?;
+        let class = StmtKind::Class {
+            name,
+            superclass,
+            methods,
+        };
+        Ok(Stmt::new(class, self.span_from(start)))
+    }
+
+    // methods are functions without the fun keyword. like a block, a
+    // missing '}' is pinned on the '{'
+    fn class_body(&mut self) -> Result<Vec<Function>> {
+        let open = self.advance().clone();
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let start = self.peek().span();
+            methods.push(self.function(start, ParseErrorKind::ExpectMethodName)?);
+        }
+        if self.is_at_end() {
+            return Err(ParseError::new(ParseErrorKind::UnclosedBlock, open));
+        }
+        self.advance();
+        Ok(methods)
+    }
+
+    // the name, parameters, and body of a function or method. the span runs
+    // from `start` to the body's closing brace
+    fn function(&mut self, start: Span, missing_name: ParseErrorKind) -> Result<Function> {
+        let name = self.identifier(missing_name)?;
+        self.consume(
+            TokenType::LeftParen,
+            ParseErrorKind::ExpectLeftParenAfterFunctionName,
+        )?;
+        let too_many = ParseErrorKind::TooManyParameters(MAX_ARITY);
+        let params = self.comma_list(too_many, |p| {
+            p.identifier(ParseErrorKind::ExpectParameterName)
+        })?;
+        self.consume(
+            TokenType::RightParen,
+            ParseErrorKind::ExpectRightParenAfterParameters,
+        )?;
+        if !self.check(&TokenType::LeftBrace) {
+            return Err(self.error(ParseErrorKind::ExpectLeftBraceBeforeBody));
+        }
+        let body = self.nested(Self::block)?;
+        Ok(Function {
+            name,
+            params,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt> {
+        let start = self.previous().span();
+        let name = self.identifier(ParseErrorKind::ExpectVariableName)?;
+        let mut initializer = None;
+        if self.match_any(&[TokenType::Equal]) {
+            initializer = Some(self.expression()?);
+        }
+        self.consume(
+            TokenType::Semicolon,
+            ParseErrorKind::ExpectSemicolonAfterVar,
+        )?;
+        let var = StmtKind::Var { name, initializer };
+        Ok(Stmt::new(var, self.span_from(start)))
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        match self.peek().tt() {
+            TokenType::For => self.for_statement(),
+            TokenType::If => self.if_statement(),
+            TokenType::Print => self.print_statement(),
+            TokenType::Return => self.return_statement(),
+            TokenType::While => self.while_statement(),
+            TokenType::LeftBrace => self.block_statement(),
+            _ => self.expression_statement(),
+        }
+    }
+
+    // there's no for node: the loop becomes the while it stands for,
+    //   { init; while (cond) { body; incr; } }
+    // with the block left out wherever a clause is. the while and the outer
+    // block span the whole loop, and a missing condition is a `true` pinned
+    // on the for keyword
+    fn for_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.advance().clone();
+        self.consume(
+            TokenType::LeftParen,
+            ParseErrorKind::ExpectLeftParenAfter(keyword.tt().clone()),
+        )?;
+        let initializer = match self.peek().tt() {
+            TokenType::Semicolon => {
+                self.advance();
+                None
+            }
+            TokenType::Var => {
+                self.advance();
+                Some(self.var_declaration()?)
+            }
+            _ => Some(self.expression_statement()?),
+        };
+        let mut condition = None;
+        if !self.check(&TokenType::Semicolon) {
+            condition = Some(self.expression()?);
+        }
+        self.consume(
+            TokenType::Semicolon,
+            ParseErrorKind::ExpectSemicolonAfterLoopCondition,
+        )?;
+        let mut increment = None;
+        if !self.check(&TokenType::RightParen) {
+            increment = Some(self.expression()?);
+        }
+        self.consume(
+            TokenType::RightParen,
+            ParseErrorKind::ExpectRightParenAfterForClauses,
+        )?;
+
+        let mut body = self.nested(Self::statement)?;
+        let span = self.span_from(keyword.span());
+        if let Some(increment) = increment {
+            let body_span = body.span.merge(increment.span);
+            let increment_span = increment.span;
+            let increment = Stmt::new(StmtKind::Expression(increment), increment_span);
+            body = Stmt::new(StmtKind::Block(vec![body, increment]), body_span);
+        }
+        let condition = condition
+            .unwrap_or_else(|| Expr::new(ExprKind::Literal(Literal::Bool(true)), keyword.span()));
+        let mut stmt = Stmt::new(
+            StmtKind::While {
+                condition,
+                body: Box::new(body),
+            },
+            span,
+        );
+        if let Some(initializer) = initializer {
+            stmt = Stmt::new(StmtKind::Block(vec![initializer, stmt]), span);
+        }
+        Ok(stmt)
+    }
+
+    // an else binds to the nearest if, since the inner if gets first go at it
+    fn if_statement(&mut self) -> Result<Stmt> {
+        let start = self.peek().span();
+        let condition = self.condition()?;
+        let then_branch = self.nested(Self::statement)?;
+        let mut else_branch = None;
+        if self.match_any(&[TokenType::Else]) {
+            else_branch = Some(Box::new(self.nested(Self::statement)?));
+        }
+        let stmt = StmtKind::If {
+            condition,
+            then_branch: Box::new(then_branch),
+            else_branch,
+        };
+        Ok(Stmt::new(stmt, self.span_from(start)))
+    }
+
+    fn print_statement(&mut self) -> Result<Stmt> {
+        let start = self.advance().span();
+        let value = self.expression()?;
+        self.consume(
+            TokenType::Semicolon,
+            ParseErrorKind::ExpectSemicolonAfterValue,
+        )?;
+        Ok(Stmt::new(StmtKind::Print(value), self.span_from(start)))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.advance().clone();
+        let mut value = None;
+        if !self.check(&TokenType::Semicolon) {
+            value = Some(self.expression()?);
+        }
+        self.consume(
+            TokenType::Semicolon,
+            ParseErrorKind::ExpectSemicolonAfterReturn,
+        )?;
+        let span = self.span_from(keyword.span());
+        Ok(Stmt::new(StmtKind::Return { keyword, value }, span))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt> {
+        let start = self.peek().span();
+        let condition = self.condition()?;
+        let body = self.nested(Self::statement)?;
+        let stmt = StmtKind::While {
+            condition,
+            body: Box::new(body),
+        };
+        Ok(Stmt::new(stmt, self.span_from(start)))
+    }
+
+    fn block_statement(&mut self) -> Result<Stmt> {
+        let start = self.peek().span();
+        let statements = self.nested(Self::block)?;
+        Ok(Stmt::new(
+            StmtKind::Block(statements),
+            self.span_from(start),
+        ))
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt> {
+        let start = self.peek().span();
+        let expr = self.expression()?;
+        self.consume(
+            TokenType::Semicolon,
+            ParseErrorKind::ExpectSemicolonAfterExpression,
+        )?;
+        Ok(Stmt::new(StmtKind::Expression(expr), self.span_from(start)))
+    }
+
+    // the parenthesized condition after an if or while keyword
+    fn condition(&mut self) -> Result<Expr> {
+        let keyword = self.advance().clone();
+        self.consume(
+            TokenType::LeftParen,
+            ParseErrorKind::ExpectLeftParenAfter(keyword.tt().clone()),
+        )?;
+        let condition = self.expression()?;
+        self.consume(
+            TokenType::RightParen,
+            ParseErrorKind::ExpectRightParenAfterCondition,
+        )?;
+        Ok(condition)
+    }
+
+    // a missing '}' is only noticed at the end of the input, far from where
+    // it went wrong, so the error points back at the '{' left open
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        let open = self.advance().clone();
+        let mut statements = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            statements.extend(self.declaration());
+        }
+        if self.is_at_end() {
+            return Err(ParseError::new(ParseErrorKind::UnclosedBlock, open));
+        }
+        self.advance();
+        Ok(statements)
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        self.nested(Self::comma)
+    }
+
+    // the comma operator evaluates both sides and keeps the right. it binds
+    // loosest of all, so argument lists, where a comma separates, start
+    // parsing below it
+    fn comma(&mut self) -> Result<Expr> {
+        self.binary(&[TokenType::Comma], Self::assignment)
+    }
+
+    // the target is parsed as an ordinary expression first, since there's
+    // no telling it apart from one until the '=' shows up
+    fn assignment(&mut self) -> Result<Expr> {
+        let expr = self.conditional()?;
+        if !self.match_any(&[TokenType::Equal]) {
+            return Ok(expr);
+        }
+        let equals = self.previous().clone();
+        let value = self.nested(Self::assignment)?;
+        let span = expr.span.merge(value.span);
+        match expr.node {
+            ExprKind::Variable(name) => Ok(Expr::new(
+                ExprKind::Assign {
+                    name,
+                    value: Box::new(value),
+                },
+                span,
+            )),
+            ExprKind::Get { object, name } => Ok(Expr::new(
+                ExprKind::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                },
+                span,
+            )),
+            // the parser isn't lost, so carry on as if the '=' weren't there
+            target => {
+                let err = ParseError::new(ParseErrorKind::InvalidAssignmentTarget, equals);
+                self.errors.push(err);
+                Ok(Expr::new(target, expr.span))
+            }
+        }
+    }
+
+    // right-associative, so a ? b : c ? d : e reads as a ? b : (c ? d : e).
+    // a '?' left without its ':' is pinned on the '?', since the ':' could
+    // have been meant to go anywhere after it
+    fn conditional(&mut self) -> Result<Expr> {
+        let condition = self.or()?;
+        if !self.match_any(&[TokenType::Question]) {
+            return Ok(condition);
+        }
+        self.gate(Extension::Conditional);
+        let question = self.previous().clone();
+        let then_branch = self.expression()?;
+        if !self.match_any(&[TokenType::Colon]) {
+            return Err(ParseError::new(
+                ParseErrorKind::ConditionalMissingColon,
+                question,
+            ));
+        }
+        let else_branch = self.nested(Self::conditional)?;
+        let span = condition.span.merge(else_branch.span);
+        let conditional = ExprKind::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        };
+        Ok(Expr::new(conditional, span))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        self.logical(TokenType::Or, Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        self.logical(TokenType::And, Self::equality)
+    }
+
+    // like binary, but kept apart since the operands may not all be evaluated
+    fn logical(&mut self, op: TokenType, operand: fn(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        let mut links = 0;
+        while self.match_any(std::slice::from_ref(&op)) {
+            self.link(&mut links)?;
+            let op = self.previous().clone();
+            let right = operand(self)?;
+            let span = expr.span.merge(right.span);
+            let logical = ExprKind::Logical {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+            expr = Expr::new(logical, span);
+        }
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> Result<Expr> {
+        self.binary(
+            &[TokenType::BangEqual, TokenType::EqualEqual],
+            Self::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        self.binary(
+            &[
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+            ],
+            Self::bit_or,
+        )
+    }
+
+    // the bitwise operators bind tighter than comparisons, unlike in c, so
+    // a & 1 == 0 means (a & 1) == 0
+    fn bit_or(&mut self) -> Result<Expr> {
+        self.binary(&[TokenType::Pipe], Self::bit_xor)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expr> {
+        self.binary(&[TokenType::Caret], Self::bit_and)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr> {
+        self.binary(&[TokenType::Ampersand], Self::shift)
+    }
+
+    fn shift(&mut self) -> Result<Expr> {
+        self.binary(
+            &[TokenType::LessLess, TokenType::GreaterGreater],
+            Self::term,
+        )
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        self.binary(&[TokenType::Minus, TokenType::Plus], Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        self.binary(
+            &[TokenType::Slash, TokenType::Star, TokenType::Percent],
+            Self::unary,
+        )
+    }
+
+    // a left-associative run of operands joined by any of ops
+    fn binary(
+        &mut self,
+        ops: &[TokenType],
+        operand: fn(&mut Self) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        let mut links = 0;
+        while self.match_any(ops) {
+            self.link(&mut links)?;
+            self.gate_operator();
+            let op = self.previous().clone();
+            let right = operand(self)?;
+            let span = expr.span.merge(right.span);
+            let binary = ExprKind::Binary {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+            expr = Expr::new(binary, span);
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.match_any(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
+            self.gate_operator();
+            let op = self.previous().clone();
+            let right = self.nested(Self::unary)?;
+            let span = op.span().merge(right.span);
+            let unary = ExprKind::Unary {
+                op,
+                right: Box::new(right),
+            };
+            return Ok(Expr::new(unary, span));
+        }
+        self.call()
+    }
+
+    // calls and property accesses, chained left to right. each one's span
+    // runs from the start of the callee or object to the closing paren or
+    // property name
+    fn call(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        let mut links = 0;
+        loop {
+            if self.match_any(&[TokenType::Dot]) {
+                self.link(&mut links)?;
+                let name = self.identifier(ParseErrorKind::ExpectPropertyName)?;
+                let span = expr.span.merge(name.span());
+                let get = ExprKind::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+                expr = Expr::new(get, span);
+                continue;
+            }
+            if !self.match_any(&[TokenType::LeftParen]) {
+                break;
+            }
+            self.link(&mut links)?;
+            let arguments = self.comma_list(ParseErrorKind::TooManyArguments(MAX_ARITY), |p| {
+                p.nested(Self::assignment)
+            })?;
+            let paren = self
+                .consume(
+                    TokenType::RightParen,
+                    ParseErrorKind::ExpectRightParenAfterArguments,
+                )?
+                .clone();
+            let span = expr.span.merge(paren.span());
+            let call = ExprKind::Call {
+                callee: Box::new(expr),
+                paren,
+                arguments,
+            };
+            expr = Expr::new(call, span);
+        }
+        Ok(expr)
+    }
+
+    // the comma separated items of an argument or parameter list, up to but
+    // not including the closing paren. there's a limit of MAX_ARITY, and a
+    // comma straight before the paren gets an error of its own, since it's
+    // an easy habit to bring over from other languages. neither stops the
+    // list being parsed
+    fn comma_list<T>(
+        &mut self,
+        too_many: ParseErrorKind,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        if self.check(&TokenType::RightParen) {
+            return Ok(items);
+        }
+        loop {
+            if items.len() == MAX_ARITY {
+                let err = self.error(too_many.clone());
+                self.errors.push(err);
+            }
+            items.push(item(self)?);
+            if !self.match_any(&[TokenType::Comma]) {
+                return Ok(items);
+            }
+            if self.check(&TokenType::RightParen) {
+                let comma = self.previous().clone();
+                self.errors
+                    .push(ParseError::new(ParseErrorKind::TrailingComma, comma));
+                return Ok(items);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let start = self.peek().span();
+        let literal = match self.peek().tt() {
+            TokenType::False => Literal::Bool(false),
+            TokenType::True => Literal::Bool(true),
+            TokenType::Nil => Literal::Nil,
+            TokenType::Number(n) => Literal::Number(*n),
+            TokenType::String(s) => Literal::String(s.clone()),
+            TokenType::RawString(s) => {
+                let literal = ExprKind::Literal(Literal::String(s.clone()));
+                self.advance();
+                self.gate(Extension::RawStrings);
+                return Ok(Expr::new(literal, start));
+            }
+            TokenType::StringPart(_) => return self.interpolation(),
+            TokenType::Identifier(_) => {
+                let name = self.advance().clone();
+                return Ok(Expr::new(ExprKind::Variable(name), start));
+            }
+            TokenType::This => {
+                let keyword = self.advance().clone();
+                return Ok(Expr::new(ExprKind::This(keyword), start));
+            }
+            TokenType::Super => {
+                let keyword = self.advance().clone();
+                self.consume(TokenType::Dot, ParseErrorKind::ExpectDotAfterSuper)?;
+                let method = self.identifier(ParseErrorKind::ExpectSuperclassMethodName)?;
+                let span = self.span_from(start);
+                return Ok(Expr::new(ExprKind::Super { keyword, method }, span));
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                let expr = self.expression()?;
+                self.consume(TokenType::RightParen, ParseErrorKind::ExpectRightParen)?;
+                let span = self.span_from(start);
+                return Ok(Expr::new(ExprKind::Grouping(Box::new(expr)), span));
+            }
+            _ => return Err(self.error(ParseErrorKind::ExpectExpression)),
+        };
+        self.advance();
+        Ok(Expr::new(ExprKind::Literal(literal), start))
+    }
+
+    // STRING_PART ( INTERP_START expression INTERP_END STRING_PART )*, as
+    // the scanner lays it out. empty pieces of text are dropped
+    fn interpolation(&mut self) -> Result<Expr> {
+        let start = self.peek().span();
+        let mut parts = Vec::new();
+        self.string_part(&mut parts);
+        while self.match_any(&[TokenType::InterpStart]) {
+            self.gate(Extension::Interpolation);
+            parts.push(self.expression()?);
+            self.consume(TokenType::InterpEnd, ParseErrorKind::ExpectInterpEnd)?;
+            self.string_part(&mut parts);
+        }
+        let span = self.span_from(start);
+        Ok(Expr::new(ExprKind::Interpolation(parts), span))
+    }
+
+    fn string_part(&mut self, parts: &mut Vec<Expr>) {
+        if let TokenType::StringPart(s) = self.peek().tt() {
+            if !s.is_empty() {
+                let literal = ExprKind::Literal(Literal::String(s.clone()));
+                parts.push(Expr::new(literal, self.peek().span()));
+            }
+            self.advance();
+        }
+    }
+
+    // runs one level deeper into the tree, refusing to go past the limit
+    // rather than overflowing the stack on adversarial input
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T>) -> Result<T> {
+        let depth = self.depth;
+        let node = self.descend().and_then(|()| parse(self));
+        self.depth = depth;
+        node
+    }
+
+    fn descend(&mut self) -> Result<()> {
+        let max = self.options.limits.max_nesting_depth;
+        if self.depth >= max {
+            return Err(self.error(ParseErrorKind::NestingTooDeep(max)));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // a loop that wraps each node it builds in the next, like 1 + 1 + ...
+    // or f()()..., only recurses to parse its operands, so it doesn't go
+    // deeper. the tree it makes is as deep as the chain is long though, and
+    // walking or dropping it recurses, so chains get a (much longer) cap.
+    // the error points at the operator just consumed
+    fn link(&mut self, links: &mut usize) -> Result<()> {
+        let max = self.options.limits.max_chain_length;
+        if *links >= max {
+            let op = self.previous().clone();
+            return Err(ParseError::new(ParseErrorKind::ChainTooLong(max), op));
+        }
+        *links += 1;
+        Ok(())
+    }
+
+    // extension syntax is parsed whatever the lang, and the error for using
+    // it in classic lox doesn't stop the parse. it points at the token just
+    // consumed
+    fn gate(&mut self, extension: Extension) {
+        if !self.options.lang.allows(extension) {
+            let kind = ParseErrorKind::ExtensionNotEnabled(extension);
+            let token = self.previous().clone();
+            self.errors.push(ParseError::new(kind, token));
+        }
+    }
+
+    // gates the operator just consumed, if it isn't one of the book's
+    fn gate_operator(&mut self) {
+        let extension = match self.previous().tt() {
+            TokenType::Comma => Extension::Comma,
+            TokenType::Percent => Extension::Remainder,
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::Tilde
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => Extension::Bitwise,
+            _ => return,
+        };
+        self.gate(extension);
+    }
+
+    // token helpers
+
+    // from start through the last token consumed
+    fn span_from(&self, start: Span) -> Span {
+        start.merge(self.previous().span())
+    }
+
+    fn identifier(&mut self, missing: ParseErrorKind) -> Result<Token> {
+        match self.peek().tt() {
+            TokenType::Identifier(_) => Ok(self.advance().clone()),
+            _ => Err(self.error(missing)),
+        }
+    }
+
+    fn match_any(&mut self, types: &[TokenType]) -> bool {
+        if types.iter().any(|tt| self.check(tt)) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    fn consume(&mut self, tt: TokenType, kind: ParseErrorKind) -> Result<&Token> {
+        if self.check(&tt) {
+            return Ok(self.advance());
+        }
+        Err(self.error(kind))
+    }
+
+    // only for tokens without a payload, which compare by kind alone
+    fn check(&self, tt: &TokenType) -> bool {
+        self.peek().tt() == tt
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+
+    fn is_at_end(&self) -> bool {
+        matches!(self.peek().tt(), TokenType::End)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.peek().clone())
+    }
+}
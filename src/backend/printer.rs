@@ -0,0 +1,203 @@
+use crate::data::ast::{Expr, ExprVisitor, Function, Literal, Stmt, StmtVisitor};
+use crate::data::span::Span;
+use crate::data::symbol::Interner;
+use crate::data::token::Token;
+
+// renders syntax trees as s-expressions, e.g. `1 + 2 * 3` as (+ 1 (* 2 3)),
+// so the structure the parser settled on is plain to see. every node is
+// (name children...), except literals, variables, and this. strings are
+// quoted, so they can't be mistaken for names. names are looked up in the
+// interner the tree's tokens were scanned with
+pub struct AstPrinter<'a> {
+    names: &'a Interner,
+}
+
+impl<'a> AstPrinter<'a> {
+    pub fn new(names: &'a Interner) -> Self {
+        Self { names }
+    }
+
+    pub fn print_stmt(&mut self, stmt: &Stmt) -> String {
+        stmt.accept(self)
+    }
+
+    pub fn print_expr(&mut self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    // (fun name (params) body...)
+    fn function(&mut self, keyword: &str, function: &Function) -> String {
+        let params: Vec<_> = function
+            .params
+            .iter()
+            .map(|p| p.lexeme(self.names))
+            .collect();
+        let mut parts = vec![
+            function.name.lexeme(self.names).into_owned(),
+            format!("({})", params.join(" ")),
+        ];
+        parts.extend(function.body.iter().map(|s| self.print_stmt(s)));
+        self.parenthesize(keyword, parts)
+    }
+
+    fn parenthesize(&self, name: &str, parts: impl IntoIterator<Item = String>) -> String {
+        let mut out = format!("({}", name);
+        for part in parts {
+            out.push(' ');
+            out.push_str(&part);
+        }
+        out.push(')');
+        out
+    }
+}
+
+impl StmtVisitor<String> for AstPrinter<'_> {
+    fn visit_expression(&mut self, _: Span, expr: &Expr) -> String {
+        let expr = self.print_expr(expr);
+        self.parenthesize(";", [expr])
+    }
+
+    fn visit_print(&mut self, _: Span, expr: &Expr) -> String {
+        let expr = self.print_expr(expr);
+        self.parenthesize("print", [expr])
+    }
+
+    fn visit_var(&mut self, _: Span, name: &Token, initializer: Option<&Expr>) -> String {
+        let mut parts = vec![name.lexeme(self.names).into_owned()];
+        parts.extend(initializer.map(|init| self.print_expr(init)));
+        self.parenthesize("var", parts)
+    }
+
+    fn visit_block(&mut self, _: Span, statements: &[Stmt]) -> String {
+        let statements: Vec<_> = statements.iter().map(|s| self.print_stmt(s)).collect();
+        self.parenthesize("block", statements)
+    }
+
+    fn visit_class(
+        &mut self,
+        _: Span,
+        name: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> String {
+        let mut parts = vec![name.lexeme(self.names).into_owned()];
+        if let Some(superclass) = superclass {
+            parts.push("<".to_string());
+            parts.push(self.print_expr(superclass));
+        }
+        parts.extend(methods.iter().map(|m| self.function("method", m)));
+        self.parenthesize("class", parts)
+    }
+
+    fn visit_function(&mut self, _: Span, function: &Function) -> String {
+        self.function("fun", function)
+    }
+
+    fn visit_if(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let mut parts = vec![self.print_expr(condition), self.print_stmt(then_branch)];
+        parts.extend(else_branch.map(|s| self.print_stmt(s)));
+        self.parenthesize("if", parts)
+    }
+
+    fn visit_return(&mut self, _: Span, _: &Token, value: Option<&Expr>) -> String {
+        let value: Option<_> = value.map(|v| self.print_expr(v));
+        self.parenthesize("return", value)
+    }
+
+    fn visit_while(&mut self, _: Span, condition: &Expr, body: &Stmt) -> String {
+        let parts = [self.print_expr(condition), self.print_stmt(body)];
+        self.parenthesize("while", parts)
+    }
+}
+
+impl ExprVisitor<String> for AstPrinter<'_> {
+    fn visit_binary(&mut self, _: Span, left: &Expr, op: &Token, right: &Expr) -> String {
+        let parts = [self.print_expr(left), self.print_expr(right)];
+        self.parenthesize(&op.lexeme(self.names), parts)
+    }
+
+    fn visit_unary(&mut self, _: Span, op: &Token, right: &Expr) -> String {
+        let right = self.print_expr(right);
+        self.parenthesize(&op.lexeme(self.names), [right])
+    }
+
+    fn visit_logical(&mut self, span: Span, left: &Expr, op: &Token, right: &Expr) -> String {
+        self.visit_binary(span, left, op, right)
+    }
+
+    fn visit_conditional(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        let parts = [
+            self.print_expr(condition),
+            self.print_expr(then_branch),
+            self.print_expr(else_branch),
+        ];
+        self.parenthesize("?:", parts)
+    }
+
+    fn visit_call(&mut self, _: Span, callee: &Expr, _: &Token, arguments: &[Expr]) -> String {
+        let mut parts = vec![self.print_expr(callee)];
+        parts.extend(arguments.iter().map(|a| self.print_expr(a)));
+        self.parenthesize("call", parts)
+    }
+
+    fn visit_get(&mut self, _: Span, object: &Expr, name: &Token) -> String {
+        let parts = [
+            self.print_expr(object),
+            name.lexeme(self.names).into_owned(),
+        ];
+        self.parenthesize(".", parts)
+    }
+
+    fn visit_set(&mut self, span: Span, object: &Expr, name: &Token, value: &Expr) -> String {
+        let parts = [self.visit_get(span, object, name), self.print_expr(value)];
+        self.parenthesize("=", parts)
+    }
+
+    fn visit_this(&mut self, _: Span, _: &Token) -> String {
+        "this".to_string()
+    }
+
+    fn visit_super(&mut self, _: Span, _: &Token, method: &Token) -> String {
+        self.parenthesize("super", [method.lexeme(self.names).into_owned()])
+    }
+
+    fn visit_grouping(&mut self, _: Span, inner: &Expr) -> String {
+        let inner = self.print_expr(inner);
+        self.parenthesize("group", [inner])
+    }
+
+    fn visit_literal(&mut self, _: Span, literal: &Literal) -> String {
+        match literal {
+            Literal::Number(n) => n.to_string(),
+            Literal::String(s) => format!("{:?}", s),
+            Literal::Bool(b) => b.to_string(),
+            Literal::Nil => "nil".to_string(),
+        }
+    }
+
+    fn visit_variable(&mut self, _: Span, name: &Token) -> String {
+        name.lexeme(self.names).into_owned()
+    }
+
+    fn visit_assign(&mut self, _: Span, name: &Token, value: &Expr) -> String {
+        let parts = [name.lexeme(self.names).into_owned(), self.print_expr(value)];
+        self.parenthesize("=", parts)
+    }
+
+    fn visit_interpolation(&mut self, _: Span, parts: &[Expr]) -> String {
+        let parts: Vec<_> = parts.iter().map(|p| self.print_expr(p)).collect();
+        self.parenthesize("str", parts)
+    }
+}
@@ -1,192 +1,947 @@
-use crate::data::payload::ScanResult;
+use std::collections::{HashMap, VecDeque};
+use std::sync::LazyLock;
+
+use memchr::{memchr, memchr2, memchr3, memchr_iter, memrchr};
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::data::edit::{TextEdit, TokenDelta};
+use crate::data::error::{LexError, LexErrorKind};
+use crate::data::line_state::{LineState, Open};
+use crate::data::options::ScannerOptions;
+use crate::data::source::FileId;
+use crate::data::span::Span;
+use crate::data::symbol::Interner;
 use crate::data::token::Token;
 use crate::data::types::TokenType;
 
-pub struct Scanner {
+pub struct Scanner<'a> {
+    file: FileId,
     source: String,
-    tokens: Vec<Token>,
-    start: usize,   // first char in scanned lexeme
-    current: usize, // char considered
-    line: i16,      // source line of current
+    names: &'a mut Interner, // where identifiers are interned
+    // scanned but not yet handed out, in source order
+    pending: VecDeque<Result<Token, LexError>>,
+    scanned: usize,    // tokens queued so far, End aside
+    checked: usize,    // source before this is known to be ascii
+    finished: bool,    // End was queued
+    start: usize,      // first byte in scanned lexeme
+    current: usize,    // byte offset of the char considered
+    line: u32,         // source line of current
+    line_start: usize, // byte offset where the line of current begins
+    start_line: u32,   // line and column of start, for the token's span
+    start_column: u32,
+    depth: usize, // currently open parens and braces
+    // one entry per open ${ ... }, innermost last: the braces opened inside
+    // it so far, and the span of its ${ for error reporting
+    interpolations: Vec<(usize, Span)>,
+    options: ScannerOptions,
+    // scanning a single line for tokenize_line, where running into the end
+    // leaves a token open instead of being an error
+    by_line: bool,
+    open: Option<Open>,
 }
 
-impl Scanner {
-    pub fn new(source: String) -> Self {
+static KEYWORDS: LazyLock<HashMap<&'static str, TokenType>> = LazyLock::new(|| {
+    HashMap::from([
+        ("and", TokenType::And),
+        ("class", TokenType::Class),
+        ("else", TokenType::Else),
+        ("false", TokenType::False),
+        ("for", TokenType::For),
+        ("fun", TokenType::Fun),
+        ("if", TokenType::If),
+        ("nil", TokenType::Nil),
+        ("or", TokenType::Or),
+        ("print", TokenType::Print),
+        ("return", TokenType::Return),
+        ("super", TokenType::Super),
+        ("this", TokenType::This),
+        ("true", TokenType::True),
+        ("var", TokenType::Var),
+        ("while", TokenType::While),
+    ])
+});
+
+// rough source bytes per token in typical lox, used to size the token
+// vector up front. overshooting a little beats regrowing a large vector
+const BYTES_PER_TOKEN: usize = 4;
+
+impl<'a> Scanner<'a> {
+    pub fn new(file: FileId, source: String, names: &'a mut Interner) -> Self {
+        Self::with_options(file, source, names, ScannerOptions::default())
+    }
+
+    pub fn with_options(
+        file: FileId,
+        source: String,
+        names: &'a mut Interner,
+        options: ScannerOptions,
+    ) -> Self {
         Self {
+            file,
             source,
-            tokens: Vec::new(),
+            names,
+            pending: VecDeque::new(),
+            scanned: 0,
+            checked: 0,
+            finished: false,
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            depth: 0,
+            interpolations: Vec::new(),
+            options,
+            by_line: false,
+            open: None,
+        }
+    }
+
+    // drains the scanner, sorting out the tokens and every error met along
+    // the way; use it as an iterator to pull tokens lazily
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<LexError>) {
+        let estimate = (self.source.len() - self.current) / BYTES_PER_TOKEN + 1;
+        let mut tokens = Vec::with_capacity(estimate);
+        let mut errors = Vec::new();
+        for result in self.by_ref() {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(err) => errors.push(err),
+            }
+        }
+        (tokens, errors)
+    }
+
+    // applies an edit to the source this scanner finished scanning, where
+    // `tokens` is what it scanned, and re-lexes only around the edit. the
+    // scan restarts a token early, since `1.` and `1.5` show that a token
+    // can depend on the text just past its end, and stops once it meets an
+    // old token again, in the same state, on a line after the edit
+    pub fn rescan(&mut self, tokens: &[Token], edit: TextEdit) -> TokenDelta {
+        let mut first = tokens.partition_point(|t| t.span().end < edit.range.start);
+        first = first.saturating_sub(1);
+
+        // restart outside interpolated strings, where a token boundary is
+        // just a boundary. walking the prefix is cheap next to lexing it
+        let (mut depth, mut interp) = tokens[..first].iter().fold((0, 0), nesting);
+        let in_string = |first: usize, interp: usize| {
+            interp != 0
+                || tokens[first].tt() == &TokenType::InterpStart
+                || tokens[first - 1].tt() == &TokenType::InterpEnd
+        };
+        while first > 0 && in_string(first, interp) {
+            first -= 1;
+            match tokens[first].tt() {
+                TokenType::InterpStart => interp -= 1,
+                TokenType::InterpEnd => interp += 1,
+                _ => {}
+            }
+        }
+        if first > 0 {
+            (depth, _) = tokens[..first].iter().fold((0, 0), nesting);
+        }
+
+        let old_newlines = memchr_iter(b'\n', self.source[edit.range.clone()].as_bytes()).count();
+        let line_shift =
+            memchr_iter(b'\n', edit.text.as_bytes()).count() as isize - old_newlines as isize;
+        let shift = edit.shift();
+        let edit_end = edit.range.start + edit.text.len();
+        self.source.replace_range(edit.range, &edit.text);
+
+        // rewind the cursor to the restart point
+        let restart = match tokens.get(first) {
+            Some(token) if first > 0 => token.span(),
+            _ => Span::new(self.file, 0, 0, 1, 1),
+        };
+        self.current = restart.start;
+        self.start = restart.start;
+        self.line = restart.line;
+        self.start_column = restart.column;
+        self.line_start =
+            memrchr(b'\n', &self.source.as_bytes()[..restart.start]).map_or(0, |nl| nl + 1);
+        self.depth = depth;
+        self.interpolations.clear();
+        self.pending.clear();
+        self.finished = false;
+        self.scanned = first;
+        self.checked = restart.start;
+        let edit_line = restart.line
+            + memchr_iter(b'\n', &self.source.as_bytes()[restart.start..edit_end]).count() as u32;
+
+        let mut fresh = Vec::new();
+        let mut errors = Vec::new();
+        let mut old = first;
+        let mut old_state = (depth, 0);
+        let mut synced = None;
+        while let Some(result) = self.next() {
+            let token = match result {
+                Ok(token) => token,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let span = token.span();
+            if span.start >= edit_end && span.line > edit_line {
+                // line up the old tokens with this one
+                let old_start = span.start.wrapping_add_signed(-shift);
+                while old < tokens.len() && tokens[old].span().start < old_start {
+                    old_state = nesting(old_state, &tokens[old]);
+                    old += 1;
+                }
+                let in_sync = tokens.get(old).is_some_and(|t| {
+                    let after = nesting(old_state, t);
+                    t.tt() == token.tt()
+                        && t.span().end.wrapping_add_signed(shift) == span.end
+                        && t.tt() != &TokenType::InterpEnd
+                        && after == (self.depth, 0)
+                        && self.interpolations.is_empty()
+                        && self.pending.is_empty()
+                });
+                if in_sync {
+                    synced = Some(old);
+                    break;
+                }
+            }
+            fresh.push(token);
+        }
+        self.pending.clear();
+        self.finished = true;
+
+        let old_end = synced.unwrap_or(tokens.len());
+        let stale_end = synced.map_or(usize::MAX, |old| tokens[old].span().start);
+        TokenDelta {
+            replaced: first..old_end,
+            tokens: fresh,
+            errors,
+            rescanned: restart.start..stale_end,
+            shift,
+            line_shift,
         }
     }
 
-    fn scan_tokens(mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        std::mem::swap(&mut tokens, &mut self.tokens);
-
-        while Self::is_at_end(self.current, self.source.len()) {
-            self.start = self.current;
-            let res = Self::scan_token(
-                &self.source,
-                self.current,
-                self.start,
-                self.line,
-                &mut tokens,
-            );
-            self.line += res.lines();
-            self.current += res.read() as usize;
-        }
-
-        tokens.push(Token::new(TokenType::End, String::from(""), self.line));
-        self.tokens = tokens;
-        self.tokens.clone()
-    }
-
-    fn scan_token(
-        source: &str,
-        current: usize,
-        start: usize,
-        line: i16,
-        tokens: &mut Vec<Token>,
-    ) -> ScanResult {
-        let mut res = ScanResult::new();
-        match Self::advance(source, current) {
-            '(' => Self::add_token(TokenType::LeftParen, tokens, start, current, source, line),
-            ')' => Self::add_token(TokenType::RightParen, tokens, start, current, source, line),
-            '{' => Self::add_token(TokenType::LeftBrace, tokens, start, current, source, line),
-            '}' => Self::add_token(TokenType::RightBrace, tokens, start, current, source, line),
-            ',' => Self::add_token(TokenType::Comma, tokens, start, current, source, line),
-            '.' => Self::add_token(TokenType::Dot, tokens, start, current, source, line),
-            '-' => Self::add_token(TokenType::Minus, tokens, start, current, source, line),
-            '+' => Self::add_token(TokenType::Plus, tokens, start, current, source, line),
-            ';' => Self::add_token(TokenType::Semicolon, tokens, start, current, source, line),
-            '*' => Self::add_token(TokenType::Star, tokens, start, current, source, line),
+    // lexes one line of a larger source, for syntax highlighters. `state` is
+    // what the line before left open (the default for a file's first line),
+    // and what this one leaves open comes back with its tokens. a token cut
+    // off by the line's end comes back as the part on this line, comments
+    // are kept, and errors are left out. offsets count from the line start
+    pub fn tokenize_line(
+        line: &str,
+        state: &LineState,
+        names: &mut Interner,
+    ) -> (Vec<Token>, LineState) {
+        let options = ScannerOptions::new().keep_comments(true);
+        let line = line.to_string();
+        let mut scanner = Scanner::with_options(FileId::default(), line, names, options);
+        scanner.by_line = true;
+        scanner.line = state.line;
+        scanner.start_line = state.line;
+        for &braces in &state.interpolations {
+            scanner.interpolations.push((braces, Span::default()));
+        }
+
+        scanner.begin_lexeme();
+        let resumed = match state.open {
+            Some(Open::String { continued }) => scanner.string(continued),
+            Some(Open::RawString) => scanner.raw_string(0),
+            Some(Open::BlockComment(depth)) => {
+                scanner.block_comment(depth).map(|()| scanner.comment())
+            }
+            None => Ok(()),
+        };
+        if let Err(err) = resumed {
+            scanner.report(err);
+        }
+
+        let tokens = scanner
+            .by_ref()
+            .filter_map(Result::ok)
+            .filter(|token| token.tt() != &TokenType::End)
+            .collect();
+        let interpolations = scanner.interpolations.iter().map(|&(braces, _)| braces);
+        let next = LineState {
+            line: scanner.line + 1,
+            interpolations: interpolations.collect(),
+            open: scanner.open,
+        };
+        (tokens, next)
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    // cursor snapshot for internal error reports
+    pub fn state(&self) -> String {
+        format!(
+            "start {}, current {}, line {}, {} tokens scanned",
+            self.start, self.current, self.line, self.scanned
+        )
+    }
+
+    // scans lexemes until at least one token or error is queued, queueing
+    // End once the source runs out. an error skips the rest of its lexeme
+    // and scanning carries on after it
+    fn fill(&mut self) {
+        while self.pending.is_empty() && !self.is_at_end() {
+            self.begin_lexeme();
+            if let Err(err) = self.scan_token() {
+                self.report(err);
+            }
+            if !self.options.allow_non_ascii {
+                self.check_ascii();
+            }
+            if self.scanned > self.options.limits.max_tokens {
+                self.truncate();
+            }
+        }
+
+        if self.pending.is_empty() {
+            if let Some(&(_, span)) = self.interpolations.last().filter(|_| !self.by_line) {
+                self.report(LexError::new(LexErrorKind::UnterminatedInterpolation, span));
+            }
+            self.begin_lexeme();
+            self.add_token(TokenType::End);
+            self.finished = true;
+        }
+    }
+
+    fn scan_token(&mut self) -> Result<(), LexError> {
+        match self.advance() {
+            '(' => {
+                self.open_nesting();
+                self.add_token(TokenType::LeftParen);
+            }
+            ')' => {
+                self.close_nesting();
+                self.add_token(TokenType::RightParen);
+            }
+            '{' => {
+                self.open_nesting();
+                if let Some((braces, _)) = self.interpolations.last_mut() {
+                    *braces += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => {
+                self.close_nesting();
+                match self.interpolations.last_mut() {
+                    // closes the ${ itself, so the string picks up again
+                    Some((0, _)) => {
+                        self.interpolations.pop();
+                        self.add_token(TokenType::InterpEnd);
+                        self.begin_lexeme();
+                        self.string(true)?;
+                    }
+                    Some((braces, _)) => {
+                        *braces -= 1;
+                        self.add_token(TokenType::RightBrace);
+                    }
+                    None => self.add_token(TokenType::RightBrace),
+                }
+            }
+            ',' => self.add_token(TokenType::Comma),
+            '.' => self.add_token(TokenType::Dot),
+            '-' => self.add_token(TokenType::Minus),
+            '+' => self.add_token(TokenType::Plus),
+            ';' => self.add_token(TokenType::Semicolon),
+            '*' => self.add_token(TokenType::Star),
+            '?' => self.add_token(TokenType::Question),
+            ':' => self.add_token(TokenType::Colon),
+            '%' => self.add_token(TokenType::Percent),
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '~' => self.add_token(TokenType::Tilde),
             '!' => {
-                let t = if Self::cond_advance(source, current, '=') {
+                let t = if self.cond_advance('=') {
                     TokenType::BangEqual
                 } else {
                     TokenType::Bang
                 };
-                Self::add_token(t, tokens, start, current, source, line);
-                res.inc_read();
-            },
+                self.add_token(t);
+            }
             '=' => {
-                let t = if Self::cond_advance(source, current, '=') {
+                let t = if self.cond_advance('=') {
                     TokenType::EqualEqual
                 } else {
                     TokenType::Equal
                 };
-                Self::add_token(t, tokens, start, current, source, line);
-                res.inc_read();
-            },
+                self.add_token(t);
+            }
             '>' => {
-                let t = if Self::cond_advance(source, current, '=') {
+                let t = if self.cond_advance('=') {
                     TokenType::GreaterEqual
+                } else if self.cond_advance('>') {
+                    TokenType::GreaterGreater
                 } else {
-                    TokenType::Equal
+                    TokenType::Greater
                 };
-                Self::add_token(t, tokens, start, current, source, line);
-                res.inc_read();
-            },
+                self.add_token(t);
+            }
             '<' => {
-                let t = if Self::cond_advance(source, current, '=') {
+                let t = if self.cond_advance('=') {
                     TokenType::LessEqual
+                } else if self.cond_advance('<') {
+                    TokenType::LessLess
                 } else {
-                    TokenType::Equal
+                    TokenType::Less
                 };
-                Self::add_token(t, tokens, start, current, source, line);
-                res.inc_read();
-            },
+                self.add_token(t);
+            }
             '/' => {
-                if Self::cond_advance(source, current, '/') {
-                    while Self::peek(current, source) != '\n' && !Self::is_at_end(current, source.len()) {
-                        res.inc_read();
-                    }
+                if self.cond_advance('/') {
+                    // the comment runs to the newline, which is left for the next token
+                    let end = self.find_byte(b'\n').unwrap_or(self.source.len());
+                    self.skip_to(end);
+                    self.comment();
+                } else if self.cond_advance('*') {
+                    self.block_comment(1)?;
+                    self.comment();
                 } else {
-                    Self::add_token(TokenType::Slash, tokens, start, current, source, line);
-                    res.inc_read();
-                };
-            },
-            '"' => {
-                let sub_res = Self::string(current, source, start);
-                res.inc_lines_by_x(sub_res.lines());
-                res.inc_read_by_x(sub_res.read());
-                if let Some(tt) = sub_res.token_to_add() {
-                    Self::add_token(tt, tokens, start, current, source, line);
+                    self.add_token(TokenType::Slash);
                 }
             }
-            ' ' | '\t' | '\r' => (),
-            '\n' => res.inc_lines(),
-            _ => println!("surface lexical error to main later"),
+            '#' if self.start == 0 && self.line == 1 && self.peek() == '!' => {
+                // a shebang line, so scripts can be run directly. like a
+                // comment, the newline is left for the next token
+                let end = self.find_byte(b'\n').unwrap_or(self.source.len());
+                self.skip_to(end);
+                self.comment();
+            }
+            '"' => self.string(false)?,
+            '`' => self.raw_string(1)?,
+            '0'..='9' => self.number()?,
+            c if Self::is_ident_start(c) => self.identifier(),
+            ' ' | '\t' | '\r' | '\n' => self.skip_whitespace(), // advance() already counted the line
+            c => return Err(self.error(LexErrorKind::UnexpectedCharacter(c))),
         }
-        res.inc_read();
-        res
+        Ok(())
     }
 
     // helpers
 
-    fn string(current: usize, source: &str, start: usize) -> ScanResult {
-        let mut res = ScanResult::new(); // let's just append to top-level response later
-        let mut loc_current = current; // local current
-        while Self::peek(loc_current, source) != '"' && !Self::is_at_end(loc_current, source.len()) {
-            if Self::peek(loc_current, source) == '\n' {
-                res.inc_lines();
+    // scans a string up to its closing quote or its next ${. strings without
+    // interpolation become a single STRING token; interpolated ones become
+    //   STRING_PART (INTERP_START tokens... INTERP_END STRING_PART)*
+    // where `continued` says this segment resumes after an INTERP_END
+    fn string(&mut self, continued: bool) -> Result<(), LexError> {
+        let mut val = String::new();
+        let mut too_long = false;
+        loop {
+            // copy everything up to the next quote, backslash, or dollar in one go
+            let rest = &self.source.as_bytes()[self.current..];
+            let Some(at) = memchr3(b'"', b'\\', b'$', rest) else {
+                if self.by_line {
+                    val.push_str(&self.source[self.current..]);
+                    self.skip_to(self.source.len());
+                    self.open = Some(Open::String { continued });
+                    break;
+                }
+                self.skip_to(self.source.len());
+                return Err(self.error(LexErrorKind::UnterminatedString));
+            };
+            let at = self.current + at;
+            val.push_str(&self.source[self.current..at]);
+            self.skip_to(at);
+            if val.len() > self.options.limits.max_string_len {
+                // report once, then drop the value so it can't keep growing
+                if !too_long {
+                    let kind = LexErrorKind::StringTooLong {
+                        len: val.len(),
+                        max: self.options.limits.max_string_len,
+                    };
+                    let err = self.error(kind);
+                    self.report(err);
+                    too_long = true;
+                }
+                val.clear();
+            }
+
+            match self.advance() {
+                '"' => break,
+                '\\' => {
+                    // a bad escape is dropped from the value, and the string goes on
+                    if let Err(err) = self.escape(at, &mut val) {
+                        self.report(err);
+                    }
+                }
+                _ if self.peek() == '{' => return self.interpolation(val),
+                dollar => val.push(dollar),
+            }
+        }
+
+        let t = if continued {
+            TokenType::StringPart(val)
+        } else {
+            TokenType::String(val)
+        };
+        self.add_token(t);
+        Ok(())
+    }
+
+    // drops whatever the last lexeme queued past the token cap and gives up
+    // on the rest of the source, rather than flag every token after it
+    fn truncate(&mut self) {
+        let max = self.options.limits.max_tokens;
+        let mut span = self.lexeme_span();
+        while self.scanned > max {
+            match self.pending.pop_back() {
+                Some(Ok(token)) => {
+                    self.scanned -= 1;
+                    span = token.span();
+                }
+                Some(Err(_)) => {}
+                None => break,
+            }
+        }
+        self.report(LexError::new(LexErrorKind::TooManyTokens(max), span));
+        self.skip_to(self.source.len());
+        self.interpolations.clear();
+    }
+
+    // comments are trivia, dropped unless a tool like a formatter wants them
+    fn comment(&mut self) {
+        if self.options.keep_comments {
+            let text = self.source[self.start..self.current].to_string();
+            self.add_token(TokenType::Comment(text));
+        }
+    }
+
+    // reports the first non-ascii char scanned since the last check, so
+    // that's at most one error per lexeme
+    fn check_ascii(&mut self) {
+        let unchecked = &self.source[self.checked..self.current];
+        if let Some(at) = unchecked.bytes().position(|b| !b.is_ascii()) {
+            let at = self.checked + at;
+            let c = self.source[at..].chars().next().unwrap();
+            // the char may sit lines back, in a string or block comment
+            let bytes = self.source.as_bytes();
+            let line = self.line - memchr_iter(b'\n', &bytes[at..self.current]).count() as u32;
+            let line_start = memrchr(b'\n', &bytes[..at]).map_or(0, |nl| nl + 1);
+            let column = self.source[line_start..at].chars().count() as u32 + 1;
+            let span = Span::new(self.file, at, at + c.len_utf8(), line, column);
+            self.report(LexError::new(LexErrorKind::NonAscii(c), span));
+        }
+        self.checked = self.current;
+    }
+
+    // `...`: no escapes or interpolation, and everything up to the next
+    // backtick is the value, newlines included. `quotes` is how much of the
+    // lexeme comes before the value, 0 when resuming one cut off by a line
+    fn raw_string(&mut self, quotes: usize) -> Result<(), LexError> {
+        let Some(close) = self.find_byte(b'`') else {
+            self.skip_to(self.source.len());
+            if self.by_line {
+                self.open = Some(Open::RawString);
+                let val = self.source[self.start + quotes..].to_string();
+                self.add_token(TokenType::RawString(val));
+                return Ok(());
+            }
+            return Err(self.error(LexErrorKind::UnterminatedString));
+        };
+        self.skip_to(close + 1);
+
+        let len = close - self.start - quotes;
+        if len > self.options.limits.max_string_len {
+            return Err(self.error(LexErrorKind::StringTooLong {
+                len,
+                max: self.options.limits.max_string_len,
+            }));
+        }
+        let val = self.source[self.start + quotes..close].to_string();
+        self.add_token(TokenType::RawString(val));
+        Ok(())
+    }
+
+    // emits the segment before a ${ and the INTERP_START for it. the cursor is
+    // just past the $
+    fn interpolation(&mut self, segment: String) -> Result<(), LexError> {
+        self.current -= 1;
+        self.add_token(TokenType::StringPart(segment));
+
+        self.begin_lexeme();
+        self.advance();
+        self.advance();
+        self.open_nesting();
+        self.add_token(TokenType::InterpStart);
+        self.interpolations.push((0, self.lexeme_span()));
+        Ok(())
+    }
+
+    // the rest of an escape sequence, whose backslash is at `at`
+    fn escape(&mut self, at: usize, val: &mut String) -> Result<(), LexError> {
+        if self.is_at_end() {
+            return Ok(()); // string() reports the missing quote
+        }
+        let span = self.span_from(at);
+        let cooked = match self.advance() {
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'u' => self.unicode_escape(span)?,
+            c => {
+                let span = Span { end: at + 1 + c.len_utf8(), ..span };
+                return Err(LexError::new(LexErrorKind::InvalidEscape(c), span));
+            }
+        };
+        val.push(cooked);
+        Ok(())
+    }
+
+    // \u{XXXX}: one to six hex digits naming a unicode scalar value
+    fn unicode_escape(&mut self, span: Span) -> Result<char, LexError> {
+        let mut cooked = None;
+        if self.cond_advance('{') {
+            let digits_start = self.current;
+            while self.peek().is_ascii_hexdigit() {
+                self.advance();
+            }
+            let digits_end = self.current;
+            if (1..=6).contains(&(digits_end - digits_start)) && self.cond_advance('}') {
+                let digits = &self.source[digits_start..digits_end];
+                cooked = u32::from_str_radix(digits, 16).ok().and_then(char::from_u32);
             }
-            res.inc_read();
-            loc_current += 1;
         }
+        cooked.ok_or_else(|| {
+            let span = Span { end: self.current, ..span };
+            LexError::new(LexErrorKind::InvalidUnicodeEscape, span)
+        })
+    }
+
+    // /* ... */, which nest: every /* inside needs its own */. `depth` is
+    // how many are open already
+    fn block_comment(&mut self, mut depth: usize) -> Result<(), LexError> {
+        while depth > 0 {
+            let Some(at) = memchr2(b'/', b'*', &self.source.as_bytes()[self.current..]) else {
+                self.skip_to(self.source.len());
+                if self.by_line {
+                    self.open = Some(Open::BlockComment(depth));
+                    return Ok(());
+                }
+                return Err(self.error(LexErrorKind::UnterminatedBlockComment));
+            };
+            let at = self.current + at;
+
+            let pair = &self.source.as_bytes()[at..];
+            if pair.starts_with(b"/*") {
+                depth += 1;
+                self.skip_to(at + 2);
+            } else if pair.starts_with(b"*/") {
+                depth -= 1;
+                self.skip_to(at + 2);
+            } else {
+                self.skip_to(at + 1);
+            }
+        }
+        Ok(())
+    }
+
+    // decimals like 12, 1.5, and 2.5e-3, or integers in hex (0x1F) and
+    // binary (0b1010). any of them may use `_` between digits
+    fn number(&mut self) -> Result<(), LexError> {
+        let radix = match (self.source.as_bytes()[self.start], self.peek()) {
+            (b'0', 'x' | 'X') => 16,
+            (b'0', 'b' | 'B') => 2,
+            _ => 10,
+        };
+        if radix == 10 {
+            self.decimal()?;
+        } else {
+            self.advance();
+            self.radix_digits(radix)?;
+        }
+
+        let text = &self.source[self.start..self.current];
+        if text.len() > self.options.limits.max_number_len {
+            return Err(self.error(LexErrorKind::NumberTooLong {
+                len: text.len(),
+                max: self.options.limits.max_number_len,
+            }));
+        }
+        self.check_separators(radix)?;
 
-        if Self::is_at_end(loc_current, source.len()) {
-            println!("Unterminated string."); // surface error here
-            return res;
+        let text = &self.source[self.start..self.current];
+        let val = if radix == 10 {
+            let digits = text.replace('_', "");
+            digits.parse().expect("scanned decimal should parse")
+        } else {
+            // past 2^53 this rounds, like any other large lox number
+            text[2..]
+                .chars()
+                .filter_map(|c| c.to_digit(radix))
+                .fold(0.0, |val, d| val * radix as f64 + d as f64)
+        };
+        self.add_token(TokenType::Number(val));
+        Ok(())
+    }
+
+    fn decimal(&mut self) -> Result<(), LexError> {
+        self.skip_digits(10);
+
+        // only a dot followed by a digit starts a fractional part, so
+        // `1.` and `1..2` leave their dots for the next token
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            self.advance();
+            self.skip_digits(10);
         }
 
-        // one more 'advance' for the closing quote
-        res.inc_read();
-        loc_current += 1;
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
+                self.advance();
+            }
+            if !self.skip_digits(10) {
+                return Err(self.error(LexErrorKind::EmptyExponent));
+            }
+        }
+        Ok(())
+    }
 
-        // trim surrounding quotes
-        let val = &source[start + 1..loc_current - 1];
-        res.set_token(TokenType::String(val.to_string()));
+    // the digits after 0x or 0b. a letter or digit glued onto them is a
+    // typo in the literal, so it's reported rather than left as a new token
+    fn radix_digits(&mut self, radix: u32) -> Result<(), LexError> {
+        let found = self.skip_digits(radix);
+        let c = self.peek();
+        if c.is_ascii_alphanumeric() {
+            let span = Span {
+                end: self.current + 1,
+                ..self.span_from(self.current)
+            };
+            while Self::is_ident_continue(self.peek()) {
+                self.advance();
+            }
+            let kind = LexErrorKind::InvalidDigit { digit: c, radix };
+            return Err(LexError::new(kind, span));
+        }
+        if !found {
+            // just the 0x or 0b, without any separators skipped after it
+            let prefix = self.source[self.start..self.start + 2].to_string();
+            return Err(self.error(LexErrorKind::EmptyRadixLiteral(prefix)));
+        }
+        Ok(())
+    }
 
-        res
+    // every `_` in the literal needs a digit on both sides, so 1_000 is fine
+    // but 1__000, 1_, 0x_1, and 1_.5 are not
+    fn check_separators(&self, radix: u32) -> Result<(), LexError> {
+        let text = &self.source.as_bytes()[self.start..self.current];
+        let is_digit = |b: Option<&u8>| b.is_some_and(|&b| (b as char).is_digit(radix));
+        let misplaced = memchr_iter(b'_', text)
+            .find(|&i| i == 0 || !is_digit(text.get(i - 1)) || !is_digit(text.get(i + 1)));
+        match misplaced {
+            Some(i) => {
+                let at = self.start + i;
+                let span = Span {
+                    end: at + 1,
+                    ..self.span_from(at)
+                };
+                Err(LexError::new(LexErrorKind::MisplacedSeparator, span))
+            }
+            None => Ok(()),
+        }
     }
 
-    fn peek(current: usize, source: &str) -> char {
-        if Self::is_at_end(current, source.len()) { 
-            return '\0';
+    fn identifier(&mut self) {
+        while Self::is_ident_continue(self.peek()) {
+            self.advance();
         }
-        return source.chars().nth(current).expect("peek machine broke");
+
+        let text = &self.source[self.start..self.current];
+        let t = match KEYWORDS.get(text) {
+            Some(keyword) => keyword.clone(),
+            None => TokenType::Identifier(self.names.intern(text)),
+        };
+        self.add_token(t);
+    }
+
+    // unicode identifiers (UAX #31) plus the leading underscore lox allows
+    fn is_ident_start(c: char) -> bool {
+        c.is_ascii_alphabetic() || c == '_' || (!c.is_ascii() && is_xid_start(c))
     }
 
-    fn is_at_end(current: usize, source_len: usize) -> bool {
-        current >= source_len
+    fn is_ident_continue(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_' || (!c.is_ascii() && is_xid_continue(c))
     }
 
-    fn cond_advance(source: &str, current: usize, expected: char) -> bool {
-        if Self::is_at_end(current, source.len()) { return false; }
-        let next = source.chars().nth(current + 1).expect("cond advance");
-        if next != expected { return false; }
+    // digits of the radix and `_` separators, saying whether any digit was seen
+    fn skip_digits(&mut self, radix: u32) -> bool {
+        let mut found = false;
+        loop {
+            match self.peek() {
+                '_' => {}
+                c if c.is_digit(radix) => found = true,
+                _ => return found,
+            }
+            self.advance();
+        }
+    }
+
+    // a recursive descent parser recurses once per level of nesting, so
+    // deep nesting is cut off here before a parser ever sees it. only the
+    // bracket that crosses the limit is reported, not every one past it
+    fn open_nesting(&mut self) {
+        self.depth += 1;
+        let max = self.options.limits.max_nesting_depth;
+        if self.depth == max + 1 {
+            let err = self.error(LexErrorKind::NestingTooDeep(max));
+            self.report(err);
+        }
+    }
+
+    fn close_nesting(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    fn peek(&self) -> char {
+        self.char_at(self.current)
+    }
+
+    fn peek_next(&self) -> char {
+        self.char_at(self.current + self.peek().len_utf8())
+    }
+
+    // lox punctuation is all ascii, so read the byte directly and only
+    // decode utf-8 for non-ascii chars in strings, comments, and identifiers
+    fn char_at(&self, offset: usize) -> char {
+        match self.source.as_bytes().get(offset) {
+            None => '\0',
+            Some(&b) if b.is_ascii() => b as char,
+            Some(_) => self.source[offset..].chars().next().unwrap(),
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    fn cond_advance(&mut self, expected: char) -> bool {
+        if self.is_at_end() || self.peek() != expected {
+            return false;
+        }
+        self.advance();
         true
     }
 
-    fn advance(source: &str, current: usize) -> char {
-        source.chars().nth(current + 1).expect("current is borked")
+    // consumes the char at current, keeping line bookkeeping in step
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+        self.current += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = self.current;
+        }
+        c
+    }
+
+    // consumes the rest of a whitespace run in one go
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source.as_bytes()[self.current..];
+        let run = rest
+            .iter()
+            .position(|b| !matches!(b, b' ' | b'\t' | b'\r' | b'\n'))
+            .unwrap_or(rest.len());
+        self.skip_to(self.current + run);
+    }
+
+    // offset of the next occurrence of an ascii byte at or after current
+    fn find_byte(&self, byte: u8) -> Option<usize> {
+        memchr(byte, &self.source.as_bytes()[self.current..]).map(|i| self.current + i)
+    }
+
+    // moves current forward to end in bulk, counting any newlines passed over.
+    // end has to be on a char boundary, which any ascii byte found by memchr is
+    fn skip_to(&mut self, end: usize) {
+        let skipped = &self.source.as_bytes()[self.current..end];
+        self.line += memchr_iter(b'\n', skipped).count() as u32;
+        if let Some(nl) = memrchr(b'\n', skipped) {
+            self.line_start = self.current + nl + 1;
+        }
+        self.current = end;
+    }
+
+    fn begin_lexeme(&mut self) {
+        // the column first, while start still marks the previous lexeme
+        self.start_column = self.column_of(self.current);
+        self.start = self.current;
+        self.start_line = self.line;
     }
 
     // no need for multiple token fns when tokentype can contain literals
-    fn add_token(
-        t: TokenType,
-        tokens: &mut Vec<Token>,
-        start: usize,
-        current: usize,
-        source: &str,
-        line: i16,
-    ) {
-        let text = source
-            .get(start..current)
-            .expect("current or start is borked");
-        tokens.push(Token::new(t, String::from(text), line));
+    fn add_token(&mut self, t: TokenType) {
+        let span = self.lexeme_span();
+        if t != TokenType::End {
+            self.scanned += 1;
+        }
+        self.pending.push_back(Ok(Token::new(t, span)));
+    }
+
+    fn error(&self, kind: LexErrorKind) -> LexError {
+        LexError::new(kind, self.lexeme_span())
+    }
+
+    // queues an error the scan can carry on past
+    fn report(&mut self, err: LexError) {
+        self.pending.push_back(Err(err));
+    }
+
+    // an empty span at offset, which must be on the current line
+    fn span_from(&self, offset: usize) -> Span {
+        Span::new(
+            self.file,
+            offset,
+            offset,
+            self.line,
+            self.column_of(offset),
+        )
+    }
+
+    // 1-based column, in chars, of an offset on the current line. counting
+    // on from the lexeme start when it's on this line keeps long lines linear
+    fn column_of(&self, offset: usize) -> u32 {
+        let (from, column) = if self.start >= self.line_start && self.start <= offset {
+            (self.start, self.start_column)
+        } else {
+            (self.line_start, 1)
+        };
+        column + self.source[from..offset].chars().count() as u32
+    }
+
+    fn lexeme_span(&self) -> Span {
+        Span::new(
+            self.file,
+            self.start,
+            self.current,
+            self.start_line,
+            self.start_column,
+        )
+    }
+}
+
+// yields tokens and errors in the order they're scanned, ending with End
+impl Iterator for Scanner<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() && !self.finished {
+            self.fill();
+        }
+        self.pending.pop_front()
+    }
+}
+
+// bracket depth and open interpolations after a token, the scanner state a
+// rescan has to match. closers saturate, like Scanner::close_nesting
+fn nesting((depth, interp): (usize, usize), token: &Token) -> (usize, usize) {
+    match token.tt() {
+        TokenType::LeftParen | TokenType::LeftBrace => (depth + 1, interp),
+        TokenType::RightParen | TokenType::RightBrace => (depth.saturating_sub(1), interp),
+        TokenType::InterpStart => (depth + 1, interp + 1),
+        TokenType::InterpEnd => (depth.saturating_sub(1), interp.saturating_sub(1)),
+        _ => (depth, interp),
     }
 }
@@ -0,0 +1,229 @@
+use crate::data::span::{Span, Spanned};
+use crate::data::token::Token;
+
+// the syntax tree the parser builds. every node carries the span of source
+// it was parsed from, and operators keep their whole token, so later phases
+// can point errors at a whole expression or just the operator in it
+pub type Expr = Spanned<ExprKind>;
+pub type Stmt = Spanned<StmtKind>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    // includes the comma operator, which keeps its right operand. see
+    // backend::arithmetic for what % and the bitwise operators do
+    Binary {
+        left: Box<Expr>,
+        op: Token,
+        right: Box<Expr>,
+    },
+    Unary {
+        op: Token,
+        right: Box<Expr>,
+    },
+    // and / or, which short-circuit
+    Logical {
+        left: Box<Expr>,
+        op: Token,
+        right: Box<Expr>,
+    },
+    // cond ? a : b, which only evaluates the branch it picks
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+    // the closing paren is kept for errors about the call as a whole
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    This(Token),
+    // super.method, always looked up with the dot
+    Super {
+        keyword: Token,
+        method: Token,
+    },
+    Grouping(Box<Expr>),
+    Literal(Literal),
+    Variable(Token),
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+    },
+    // "a ${b} c": the literal pieces and the embedded expressions, in order
+    Interpolation(Vec<Expr>),
+}
+
+// a statement's span includes its terminating semicolon, if it has one
+#[derive(Clone, Debug, PartialEq)]
+pub enum StmtKind {
+    Expression(Expr),
+    Print(Expr),
+    Var {
+        name: Token,
+        initializer: Option<Expr>,
+    },
+    Block(Vec<Stmt>),
+    Class {
+        name: Token,
+        superclass: Option<Expr>, // always an ExprKind::Variable
+        methods: Vec<Function>,
+    },
+    Function(Function),
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    // for loops are desugared into this, see Parser::for_statement
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+    },
+}
+
+// a function declaration or a method. the span covers all of it, from
+// `fun` or the method name to the closing brace, the same as the span of
+// the StmtKind::Function it's in
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Nil,
+}
+
+// one method per kind of node, for passes over the tree. accept picks the
+// method and hands it the node's span and fields. a visitor recurses by
+// calling accept on the children it wants to visit
+pub trait ExprVisitor<R> {
+    fn visit_binary(&mut self, span: Span, left: &Expr, op: &Token, right: &Expr) -> R;
+    fn visit_unary(&mut self, span: Span, op: &Token, right: &Expr) -> R;
+    fn visit_logical(&mut self, span: Span, left: &Expr, op: &Token, right: &Expr) -> R;
+    fn visit_conditional(
+        &mut self,
+        span: Span,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> R;
+    fn visit_call(&mut self, span: Span, callee: &Expr, paren: &Token, arguments: &[Expr]) -> R;
+    fn visit_get(&mut self, span: Span, object: &Expr, name: &Token) -> R;
+    fn visit_set(&mut self, span: Span, object: &Expr, name: &Token, value: &Expr) -> R;
+    fn visit_this(&mut self, span: Span, keyword: &Token) -> R;
+    fn visit_super(&mut self, span: Span, keyword: &Token, method: &Token) -> R;
+    fn visit_grouping(&mut self, span: Span, inner: &Expr) -> R;
+    fn visit_literal(&mut self, span: Span, literal: &Literal) -> R;
+    fn visit_variable(&mut self, span: Span, name: &Token) -> R;
+    fn visit_assign(&mut self, span: Span, name: &Token, value: &Expr) -> R;
+    fn visit_interpolation(&mut self, span: Span, parts: &[Expr]) -> R;
+}
+
+pub trait StmtVisitor<R> {
+    fn visit_expression(&mut self, span: Span, expr: &Expr) -> R;
+    fn visit_print(&mut self, span: Span, expr: &Expr) -> R;
+    fn visit_var(&mut self, span: Span, name: &Token, initializer: Option<&Expr>) -> R;
+    fn visit_block(&mut self, span: Span, statements: &[Stmt]) -> R;
+    fn visit_class(
+        &mut self,
+        span: Span,
+        name: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> R;
+    fn visit_function(&mut self, span: Span, function: &Function) -> R;
+    fn visit_if(
+        &mut self,
+        span: Span,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> R;
+    fn visit_return(&mut self, span: Span, keyword: &Token, value: Option<&Expr>) -> R;
+    fn visit_while(&mut self, span: Span, condition: &Expr, body: &Stmt) -> R;
+}
+
+impl Expr {
+    pub fn accept<R>(&self, visitor: &mut impl ExprVisitor<R>) -> R {
+        let span = self.span;
+        match &self.node {
+            ExprKind::Binary { left, op, right } => visitor.visit_binary(span, left, op, right),
+            ExprKind::Unary { op, right } => visitor.visit_unary(span, op, right),
+            ExprKind::Logical { left, op, right } => visitor.visit_logical(span, left, op, right),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_conditional(span, condition, then_branch, else_branch),
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+            } => visitor.visit_call(span, callee, paren, arguments),
+            ExprKind::Get { object, name } => visitor.visit_get(span, object, name),
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => visitor.visit_set(span, object, name, value),
+            ExprKind::This(keyword) => visitor.visit_this(span, keyword),
+            ExprKind::Super { keyword, method } => visitor.visit_super(span, keyword, method),
+            ExprKind::Grouping(inner) => visitor.visit_grouping(span, inner),
+            ExprKind::Literal(literal) => visitor.visit_literal(span, literal),
+            ExprKind::Variable(name) => visitor.visit_variable(span, name),
+            ExprKind::Assign { name, value } => visitor.visit_assign(span, name, value),
+            ExprKind::Interpolation(parts) => visitor.visit_interpolation(span, parts),
+        }
+    }
+}
+
+impl Stmt {
+    pub fn accept<R>(&self, visitor: &mut impl StmtVisitor<R>) -> R {
+        let span = self.span;
+        match &self.node {
+            StmtKind::Expression(expr) => visitor.visit_expression(span, expr),
+            StmtKind::Print(expr) => visitor.visit_print(span, expr),
+            StmtKind::Var { name, initializer } => {
+                visitor.visit_var(span, name, initializer.as_ref())
+            }
+            StmtKind::Block(statements) => visitor.visit_block(span, statements),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => visitor.visit_class(span, name, superclass.as_ref(), methods),
+            StmtKind::Function(function) => visitor.visit_function(span, function),
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_if(span, condition, then_branch, else_branch.as_deref()),
+            StmtKind::Return { keyword, value } => {
+                visitor.visit_return(span, keyword, value.as_ref())
+            }
+            StmtKind::While { condition, body } => visitor.visit_while(span, condition, body),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+use crate::data::error::LexError;
+use crate::data::token::Token;
+
+// a change to source text: the bytes in `range` are replaced by `text`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+
+    // bytes added by the edit, negative when it removes more than it inserts
+    pub fn shift(&self) -> isize {
+        self.text.len() as isize - self.range.len() as isize
+    }
+}
+
+// what an edit did to a scanned token list, see Scanner::rescan. the old
+// tokens in `replaced` give way to `tokens`, and every token after them
+// moves by `shift` bytes and `line_shift` lines
+#[derive(Clone, Debug)]
+pub struct TokenDelta {
+    pub replaced: Range<usize>, // indices into the old token list
+    pub tokens: Vec<Token>,
+    pub errors: Vec<LexError>,   // found in the rescanned text
+    pub rescanned: Range<usize>, // old byte offsets whose errors are stale
+    pub shift: isize,
+    pub line_shift: isize,
+}
+
+impl TokenDelta {
+    // brings the token and error lists of the old text up to date. errors
+    // end up ordered by where they start
+    pub fn apply(self, tokens: &mut Vec<Token>, errors: &mut Vec<LexError>) {
+        let after = self.replaced.start + self.tokens.len();
+        tokens.splice(self.replaced, self.tokens);
+        for token in &mut tokens[after..] {
+            token.shift(self.shift, self.line_shift);
+        }
+
+        errors.retain(|err| !self.rescanned.contains(&err.span.start));
+        for err in errors.iter_mut() {
+            if err.span.start >= self.rescanned.end {
+                err.span.shift(self.shift, self.line_shift);
+            }
+        }
+        errors.extend(self.errors);
+        errors.sort_by_key(|err| err.span.start);
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::data::messages::{render, Message};
+use crate::data::options::Extension;
+use crate::data::span::Span;
+use crate::data::token::Token;
+use crate::data::types::TokenType;
+
+// every error the pipeline can hand back to a caller. each phase gets its own
+// variant as it lands, wrapping that phase's error type
+#[derive(Debug, Error)]
+pub enum LoxError {
+    #[error(transparent)]
+    Lex(#[from] LexError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+impl LoxError {
+    pub fn span(&self) -> Span {
+        match self {
+            LoxError::Lex(err) => err.span(),
+            LoxError::Parse(err) => err.span(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+#[error("{kind}")]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum LexErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    InvalidEscape(char),
+    InvalidUnicodeEscape,
+    UnterminatedInterpolation,
+    UnterminatedBlockComment,
+    StringTooLong { len: usize, max: usize },
+    NumberTooLong { len: usize, max: usize },
+    EmptyRadixLiteral(String),
+    EmptyExponent,
+    InvalidDigit { digit: char, radix: u32 },
+    MisplacedSeparator,
+    NestingTooDeep(usize),
+    TooManyTokens(usize),
+    NonAscii(char),
+}
+
+// the wording comes from the message catalog, see data::messages
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            LexErrorKind::UnexpectedCharacter(c) => {
+                render(Message::UnexpectedCharacter, &[("char", c)])
+            }
+            LexErrorKind::UnterminatedString => render(Message::UnterminatedString, &[]),
+            LexErrorKind::InvalidEscape(c) => render(Message::InvalidEscape, &[("char", c)]),
+            LexErrorKind::InvalidUnicodeEscape => render(Message::InvalidUnicodeEscape, &[]),
+            LexErrorKind::UnterminatedInterpolation => {
+                render(Message::UnterminatedInterpolation, &[])
+            }
+            LexErrorKind::UnterminatedBlockComment => {
+                render(Message::UnterminatedBlockComment, &[])
+            }
+            LexErrorKind::StringTooLong { len, max } => {
+                render(Message::StringTooLong, &[("len", len), ("max", max)])
+            }
+            LexErrorKind::NumberTooLong { len, max } => {
+                render(Message::NumberTooLong, &[("len", len), ("max", max)])
+            }
+            LexErrorKind::EmptyRadixLiteral(prefix) => {
+                render(Message::EmptyRadixLiteral, &[("prefix", prefix)])
+            }
+            LexErrorKind::EmptyExponent => render(Message::EmptyExponent, &[]),
+            LexErrorKind::InvalidDigit { digit, radix } => {
+                render(Message::InvalidDigit, &[("digit", digit), ("radix", radix)])
+            }
+            LexErrorKind::MisplacedSeparator => render(Message::MisplacedSeparator, &[]),
+            LexErrorKind::TooManyTokens(max) => render(Message::TooManyTokens, &[("max", max)]),
+            LexErrorKind::NonAscii(c) => render(Message::NonAscii, &[("char", c)]),
+            LexErrorKind::NestingTooDeep(max) => render(Message::NestingTooDeep, &[("max", max)]),
+        };
+        f.write_str(&text)
+    }
+}
+
+// a syntax error, anchored on the token the parser couldn't use
+#[derive(Clone, Debug, Error, PartialEq)]
+#[error("{kind}")]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub token: Token,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, token: Token) -> Self {
+        Self { kind, token }
+    }
+
+    pub fn span(&self) -> Span {
+        self.token.span()
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum ParseErrorKind {
+    ExpectExpression,
+    ExpectRightParen,
+    ExpectInterpEnd,
+    ExpectEnd,
+    ExpectVariableName,
+    ExpectSemicolonAfterValue,
+    ExpectSemicolonAfterExpression,
+    ExpectSemicolonAfterVar,
+    InvalidAssignmentTarget,
+    UnclosedBlock,
+    ExpectLeftParenAfter(TokenType), // the keyword
+    ExpectRightParenAfterCondition,
+    ExpectSemicolonAfterLoopCondition,
+    ExpectRightParenAfterForClauses,
+    ExpectFunctionName,
+    ExpectLeftParenAfterFunctionName,
+    ExpectParameterName,
+    ExpectRightParenAfterParameters,
+    ExpectLeftBraceBeforeBody,
+    ExpectRightParenAfterArguments,
+    ExpectSemicolonAfterReturn,
+    TooManyArguments(usize),
+    TooManyParameters(usize),
+    TrailingComma,
+    ExpectClassName,
+    ExpectSuperclassName,
+    ExpectLeftBraceBeforeClassBody,
+    ExpectMethodName,
+    ExpectPropertyName,
+    ExpectDotAfterSuper,
+    ExpectSuperclassMethodName,
+    ConditionalMissingColon,
+    ExtensionNotEnabled(Extension),
+    NestingTooDeep(usize),
+    ChainTooLong(usize),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            ParseErrorKind::ExpectExpression => render(Message::ExpectExpression, &[]),
+            ParseErrorKind::ExpectRightParen => render(Message::ExpectRightParen, &[]),
+            ParseErrorKind::ExpectInterpEnd => render(Message::ExpectInterpEnd, &[]),
+            ParseErrorKind::ExpectEnd => render(Message::ExpectEnd, &[]),
+            ParseErrorKind::ExpectVariableName => render(Message::ExpectVariableName, &[]),
+            ParseErrorKind::ExpectSemicolonAfterValue => {
+                render(Message::ExpectSemicolonAfterValue, &[])
+            }
+            ParseErrorKind::ExpectSemicolonAfterExpression => {
+                render(Message::ExpectSemicolonAfterExpression, &[])
+            }
+            ParseErrorKind::ExpectSemicolonAfterVar => {
+                render(Message::ExpectSemicolonAfterVar, &[])
+            }
+            ParseErrorKind::InvalidAssignmentTarget => {
+                render(Message::InvalidAssignmentTarget, &[])
+            }
+            ParseErrorKind::UnclosedBlock => render(Message::UnclosedBlock, &[]),
+            ParseErrorKind::ExpectLeftParenAfter(keyword) => render(
+                Message::ExpectLeftParenAfter,
+                &[("keyword", &keyword.fixed_lexeme().unwrap_or_default())],
+            ),
+            ParseErrorKind::ExpectRightParenAfterCondition => {
+                render(Message::ExpectRightParenAfterCondition, &[])
+            }
+            ParseErrorKind::ExpectSemicolonAfterLoopCondition => {
+                render(Message::ExpectSemicolonAfterLoopCondition, &[])
+            }
+            ParseErrorKind::ExpectRightParenAfterForClauses => {
+                render(Message::ExpectRightParenAfterForClauses, &[])
+            }
+            ParseErrorKind::ExpectFunctionName => render(Message::ExpectFunctionName, &[]),
+            ParseErrorKind::ExpectLeftParenAfterFunctionName => {
+                render(Message::ExpectLeftParenAfterFunctionName, &[])
+            }
+            ParseErrorKind::ExpectParameterName => render(Message::ExpectParameterName, &[]),
+            ParseErrorKind::ExpectRightParenAfterParameters => {
+                render(Message::ExpectRightParenAfterParameters, &[])
+            }
+            ParseErrorKind::ExpectLeftBraceBeforeBody => {
+                render(Message::ExpectLeftBraceBeforeBody, &[])
+            }
+            ParseErrorKind::ExpectRightParenAfterArguments => {
+                render(Message::ExpectRightParenAfterArguments, &[])
+            }
+            ParseErrorKind::ExpectSemicolonAfterReturn => {
+                render(Message::ExpectSemicolonAfterReturn, &[])
+            }
+            ParseErrorKind::TooManyArguments(max) => {
+                render(Message::TooManyArguments, &[("max", max)])
+            }
+            ParseErrorKind::TooManyParameters(max) => {
+                render(Message::TooManyParameters, &[("max", max)])
+            }
+            ParseErrorKind::TrailingComma => render(Message::TrailingComma, &[]),
+            ParseErrorKind::ExpectClassName => render(Message::ExpectClassName, &[]),
+            ParseErrorKind::ExpectSuperclassName => render(Message::ExpectSuperclassName, &[]),
+            ParseErrorKind::ExpectLeftBraceBeforeClassBody => {
+                render(Message::ExpectLeftBraceBeforeClassBody, &[])
+            }
+            ParseErrorKind::ExpectMethodName => render(Message::ExpectMethodName, &[]),
+            ParseErrorKind::ExpectPropertyName => render(Message::ExpectPropertyName, &[]),
+            ParseErrorKind::ExpectDotAfterSuper => render(Message::ExpectDotAfterSuper, &[]),
+            ParseErrorKind::ExpectSuperclassMethodName => {
+                render(Message::ExpectSuperclassMethodName, &[])
+            }
+            ParseErrorKind::ConditionalMissingColon => {
+                render(Message::ConditionalMissingColon, &[])
+            }
+            ParseErrorKind::ExtensionNotEnabled(extension) => {
+                render(Message::ExtensionNotEnabled, &[("extension", extension)])
+            }
+            ParseErrorKind::NestingTooDeep(max) => {
+                render(Message::ExpressionTooDeep, &[("max", max)])
+            }
+            ParseErrorKind::ChainTooLong(max) => render(Message::ChainTooLong, &[("max", max)]),
+        };
+        f.write_str(&text)
+    }
+}
@@ -0,0 +1,23 @@
+// hard caps for untrusted input. the defaults are far past anything a
+// hand-written program needs, they only exist to stop adversarial sources
+// from exhausting memory or the stack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub max_tokens: usize,        // tokens in one scan, not counting EOF
+    pub max_string_len: usize,    // bytes between the quotes
+    pub max_number_len: usize,    // digits and dot of a number literal
+    pub max_nesting_depth: usize, // open parens and braces at once
+    pub max_chain_length: usize,  // operators or calls in a row, like 1 + 1 + ...
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tokens: 64 * 1024 * 1024,
+            max_string_len: 16 * 1024 * 1024,
+            max_number_len: 1024,
+            max_nesting_depth: 256,
+            max_chain_length: 4096,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// what a line leaves open for the next one, see Scanner::tokenize_line.
+// highlighters keep one per line, and only rescan past an edited line
+// while the state it hands on keeps changing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineState {
+    pub line: u32, // 1-based number of the line this state leads into
+    // the braces opened inside each unclosed ${ ... }, innermost last
+    pub interpolations: Vec<usize>,
+    pub open: Option<Open>,
+}
+
+impl Default for LineState {
+    // the start of a file
+    fn default() -> Self {
+        Self {
+            line: 1,
+            interpolations: Vec::new(),
+            open: None,
+        }
+    }
+}
+
+// a token still running when its line ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Open {
+    // `continued` when the string picks up after an interpolation, which
+    // makes its pieces STRING_PARTs
+    String { continued: bool },
+    RawString,
+    BlockComment(usize), // nesting depth
+}
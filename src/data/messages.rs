@@ -0,0 +1,301 @@
+use std::fmt::{Display, Write};
+use std::sync::OnceLock;
+
+// every user-facing diagnostic, so the wording lives in one place and can be
+// translated. templates name their parameters in braces, and `{{` / `}}`
+// stand for literal braces. the template does any quoting, never the caller
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Message {
+    // lexer
+    UnexpectedCharacter, // char
+    UnterminatedString,
+    InvalidEscape, // char
+    InvalidUnicodeEscape,
+    UnterminatedInterpolation,
+    UnterminatedBlockComment,
+    StringTooLong,     // len, max
+    NumberTooLong,     // len, max
+    EmptyRadixLiteral, // prefix
+    EmptyExponent,
+    InvalidDigit, // digit, radix
+    MisplacedSeparator,
+    NestingTooDeep, // max
+    TooManyTokens,  // max
+    NonAscii,       // char
+
+    // parser
+    ExpectExpression,
+    ExpectRightParen,
+    ExpectInterpEnd,
+    ExpectEnd,
+    ExpectVariableName,
+    ExpectSemicolonAfterValue,
+    ExpectSemicolonAfterExpression,
+    ExpectSemicolonAfterVar,
+    InvalidAssignmentTarget,
+    UnclosedBlock,
+    ExpectLeftParenAfter, // keyword
+    ExpectRightParenAfterCondition,
+    ExpectSemicolonAfterLoopCondition,
+    ExpectRightParenAfterForClauses,
+    ExpectFunctionName,
+    ExpectLeftParenAfterFunctionName,
+    ExpectParameterName,
+    ExpectRightParenAfterParameters,
+    ExpectLeftBraceBeforeBody,
+    ExpectRightParenAfterArguments,
+    ExpectSemicolonAfterReturn,
+    TooManyArguments,  // max
+    TooManyParameters, // max
+    TrailingComma,
+    ExpectClassName,
+    ExpectSuperclassName,
+    ExpectLeftBraceBeforeClassBody,
+    ExpectMethodName,
+    ExpectPropertyName,
+    ExpectDotAfterSuper,
+    ExpectSuperclassMethodName,
+    ConditionalMissingColon,
+    ExtensionNotEnabled, // extension
+    ExpressionTooDeep,   // max
+    ChainTooLong,        // max
+
+    // driver
+    Usage,
+    Error,    // message
+    ErrorAt,  // position, location, message
+    Position, // line, column
+    InFile,   // file
+    AtToken,  // lexeme
+    AtEnd,
+    CantRead, // path, reason
+    NoSuchFile,
+    PermissionDenied,
+    IsADirectory,
+    InvalidUtf8,      // path, line, column
+    CantWrite,        // path, reason
+    RecordingStopped, // reason
+    ReplCommands,     // command
+    InternalError,    // phase, message
+    ReportBug,
+    PanickedAt, // site
+    PhaseState, // phase, state
+}
+
+impl Message {
+    // stable key for catalogs kept outside the source, e.g. in a file
+    pub fn id(self) -> &'static str {
+        match self {
+            Message::UnexpectedCharacter => "unexpected-character",
+            Message::UnterminatedString => "unterminated-string",
+            Message::InvalidEscape => "invalid-escape",
+            Message::InvalidUnicodeEscape => "invalid-unicode-escape",
+            Message::UnterminatedInterpolation => "unterminated-interpolation",
+            Message::UnterminatedBlockComment => "unterminated-block-comment",
+            Message::StringTooLong => "string-too-long",
+            Message::NumberTooLong => "number-too-long",
+            Message::EmptyRadixLiteral => "empty-radix-literal",
+            Message::EmptyExponent => "empty-exponent",
+            Message::InvalidDigit => "invalid-digit",
+            Message::MisplacedSeparator => "misplaced-separator",
+            Message::NestingTooDeep => "nesting-too-deep",
+            Message::TooManyTokens => "too-many-tokens",
+            Message::NonAscii => "non-ascii",
+            Message::ExpectExpression => "expect-expression",
+            Message::ExpectRightParen => "expect-right-paren",
+            Message::ExpectInterpEnd => "expect-interp-end",
+            Message::ExpectEnd => "expect-end",
+            Message::ExpectVariableName => "expect-variable-name",
+            Message::ExpectSemicolonAfterValue => "expect-semicolon-after-value",
+            Message::ExpectSemicolonAfterExpression => "expect-semicolon-after-expression",
+            Message::ExpectSemicolonAfterVar => "expect-semicolon-after-var",
+            Message::InvalidAssignmentTarget => "invalid-assignment-target",
+            Message::UnclosedBlock => "unclosed-block",
+            Message::ExpectLeftParenAfter => "expect-left-paren-after",
+            Message::ExpectRightParenAfterCondition => "expect-right-paren-after-condition",
+            Message::ExpectSemicolonAfterLoopCondition => "expect-semicolon-after-loop-condition",
+            Message::ExpectRightParenAfterForClauses => "expect-right-paren-after-for-clauses",
+            Message::ExpectFunctionName => "expect-function-name",
+            Message::ExpectLeftParenAfterFunctionName => "expect-left-paren-after-function-name",
+            Message::ExpectParameterName => "expect-parameter-name",
+            Message::ExpectRightParenAfterParameters => "expect-right-paren-after-parameters",
+            Message::ExpectLeftBraceBeforeBody => "expect-left-brace-before-body",
+            Message::ExpectRightParenAfterArguments => "expect-right-paren-after-arguments",
+            Message::ExpectSemicolonAfterReturn => "expect-semicolon-after-return",
+            Message::TooManyArguments => "too-many-arguments",
+            Message::TooManyParameters => "too-many-parameters",
+            Message::TrailingComma => "trailing-comma",
+            Message::ExpectClassName => "expect-class-name",
+            Message::ExpectSuperclassName => "expect-superclass-name",
+            Message::ExpectLeftBraceBeforeClassBody => "expect-left-brace-before-class-body",
+            Message::ExpectMethodName => "expect-method-name",
+            Message::ExpectPropertyName => "expect-property-name",
+            Message::ExpectDotAfterSuper => "expect-dot-after-super",
+            Message::ExpectSuperclassMethodName => "expect-superclass-method-name",
+            Message::ConditionalMissingColon => "conditional-missing-colon",
+            Message::ExtensionNotEnabled => "extension-not-enabled",
+            Message::ExpressionTooDeep => "expression-too-deep",
+            Message::ChainTooLong => "chain-too-long",
+            Message::Usage => "usage",
+            Message::Error => "error",
+            Message::ErrorAt => "error-at",
+            Message::Position => "position",
+            Message::InFile => "in-file",
+            Message::AtToken => "at-token",
+            Message::AtEnd => "at-end",
+            Message::CantRead => "cant-read",
+            Message::NoSuchFile => "no-such-file",
+            Message::PermissionDenied => "permission-denied",
+            Message::IsADirectory => "is-a-directory",
+            Message::InvalidUtf8 => "invalid-utf8",
+            Message::CantWrite => "cant-write",
+            Message::RecordingStopped => "recording-stopped",
+            Message::ReplCommands => "repl-commands",
+            Message::InternalError => "internal-error",
+            Message::ReportBug => "report-bug",
+            Message::PanickedAt => "panicked-at",
+            Message::PhaseState => "phase-state",
+        }
+    }
+
+    pub fn english(self) -> &'static str {
+        match self {
+            Message::UnexpectedCharacter => "Unexpected character '{char}'.",
+            Message::UnterminatedString => "Unterminated string.",
+            Message::InvalidEscape => "Invalid escape sequence '\\{char}'.",
+            Message::InvalidUnicodeEscape => {
+                "Invalid unicode escape; expected \\u{{...}} with 1 to 6 hex digits naming a code point."
+            }
+            Message::UnterminatedInterpolation => {
+                "Unterminated interpolation; expected '}}' to close '${{'."
+            }
+            Message::UnterminatedBlockComment => "Unterminated block comment.",
+            Message::StringTooLong => "String literal is {len} bytes long; the limit is {max}.",
+            Message::NumberTooLong => {
+                "Number literal is {len} characters long; the limit is {max}."
+            }
+            Message::EmptyRadixLiteral => "Expected digits after '{prefix}'.",
+            Message::EmptyExponent => "Expected digits in the exponent.",
+            Message::InvalidDigit => "Invalid digit '{digit}' in a base {radix} literal.",
+            Message::MisplacedSeparator => "Digit separator '_' must sit between two digits.",
+            Message::NestingTooDeep => {
+                "Too many nested parentheses and braces; the limit is {max}."
+            }
+            Message::TooManyTokens => "Too many tokens; the limit is {max}.",
+            Message::NonAscii => "Non-ASCII character '{char}' isn't allowed here.",
+            Message::ExpectExpression => "Expect expression.",
+            Message::ExpectRightParen => "Expect ')' after expression.",
+            Message::ExpectInterpEnd => "Expect '}}' after interpolated expression.",
+            Message::ExpectEnd => "Expect end of input after expression.",
+            Message::ExpectVariableName => "Expect variable name.",
+            Message::ExpectSemicolonAfterValue => "Expect ';' after value.",
+            Message::ExpectSemicolonAfterExpression => "Expect ';' after expression.",
+            Message::ExpectSemicolonAfterVar => "Expect ';' after variable declaration.",
+            Message::InvalidAssignmentTarget => "Invalid assignment target.",
+            Message::UnclosedBlock => "Expect '}}' to close this block.",
+            Message::ExpectLeftParenAfter => "Expect '(' after '{keyword}'.",
+            Message::ExpectRightParenAfterCondition => "Expect ')' after condition.",
+            Message::ExpectSemicolonAfterLoopCondition => "Expect ';' after loop condition.",
+            Message::ExpectRightParenAfterForClauses => "Expect ')' after for clauses.",
+            Message::ExpectFunctionName => "Expect function name.",
+            Message::ExpectLeftParenAfterFunctionName => "Expect '(' after function name.",
+            Message::ExpectParameterName => "Expect parameter name.",
+            Message::ExpectRightParenAfterParameters => "Expect ')' after parameters.",
+            Message::ExpectLeftBraceBeforeBody => "Expect '{{' before function body.",
+            Message::ExpectRightParenAfterArguments => "Expect ')' after arguments.",
+            Message::ExpectSemicolonAfterReturn => "Expect ';' after return value.",
+            Message::TooManyArguments => "Can't have more than {max} arguments.",
+            Message::TooManyParameters => "Can't have more than {max} parameters.",
+            Message::TrailingComma => "Unexpected ',' before ')'; lox doesn't allow a trailing comma.",
+            Message::ExpectClassName => "Expect class name.",
+            Message::ExpectSuperclassName => "Expect superclass name.",
+            Message::ExpectLeftBraceBeforeClassBody => "Expect '{{' before class body.",
+            Message::ExpectMethodName => "Expect method name.",
+            Message::ExpectPropertyName => "Expect property name after '.'.",
+            Message::ExpectDotAfterSuper => "Expect '.' after 'super'.",
+            Message::ExpectSuperclassMethodName => "Expect superclass method name.",
+            Message::ConditionalMissingColon => {
+                "Conditional expression is missing its ':' and else branch."
+            }
+            Message::ExtensionNotEnabled => {
+                "Can't use {extension} in lox-classic; pass --lang=loxrs-extended to allow it."
+            }
+            Message::ExpressionTooDeep => "Code nested too deeply; the limit is {max} levels.",
+            Message::ChainTooLong => "Too many operators or calls in a row; the limit is {max}.",
+            Message::Usage => "Usage: loxrs [--tokens] [--print-ast] [--debug-ast] [--stats] [--lang=lox-classic|loxrs-extended] [script]",
+            Message::Error => "Error: {message}",
+            Message::ErrorAt => "[line {position}] Error{location}: {message}",
+            Message::Position => "{line}:{column}",
+            Message::InFile => " in {file}",
+            Message::AtToken => " at '{lexeme}'",
+            Message::AtEnd => " at end",
+            Message::CantRead => "can't read '{path}': {reason}",
+            Message::NoSuchFile => "no such file",
+            Message::PermissionDenied => "permission denied",
+            Message::IsADirectory => "is a directory",
+            Message::InvalidUtf8 => {
+                "'{path}' is not valid UTF-8 (first bad byte at line {line}, column {column})"
+            }
+            Message::CantWrite => "can't write '{path}': {reason}",
+            Message::RecordingStopped => "Recording stopped: {reason}",
+            Message::ReplCommands => {
+                "Unknown command ':{command}'. Commands are :record <file>, :stop, and :replay <file>."
+            }
+            Message::InternalError => "internal error in {phase}: {message}",
+            Message::ReportBug => {
+                "note: this is a bug in loxrs, not in your program. please report it at https://github.com/gibbyfree/loxrs/issues"
+            }
+            Message::PanickedAt => "note: panicked at {site}",
+            Message::PhaseState => "note: {phase} state: {state}",
+        }
+    }
+}
+
+// the l10n hook. a catalog supplies templates for the messages it knows;
+// anything it leaves out falls back to english
+pub trait Catalog: Send + Sync {
+    fn template(&self, msg: Message) -> Option<&str>;
+}
+
+static CATALOG: OnceLock<Box<dyn Catalog>> = OnceLock::new();
+
+// swaps in a translation for the rest of the process. only the first
+// install takes; later ones are handed back
+pub fn install(catalog: Box<dyn Catalog>) -> Result<(), Box<dyn Catalog>> {
+    CATALOG.set(catalog)
+}
+
+pub fn render(msg: Message, args: &[(&str, &dyn Display)]) -> String {
+    let template = CATALOG
+        .get()
+        .and_then(|catalog| catalog.template(msg))
+        .unwrap_or_else(|| msg.english());
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(at) = rest.find(['{', '}']) {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        // a placeholder naming an unknown parameter is left as written
+        let close = rest.find('}').filter(|_| rest.starts_with('{'));
+        let arg = close.and_then(|close| args.iter().find(|(name, _)| *name == &rest[1..close]));
+        match (close, arg) {
+            (Some(close), Some((_, value))) => {
+                let _ = write!(out, "{}", value);
+                rest = &rest[close + 1..];
+            }
+            _ => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -1,3 +1,12 @@
 pub mod types;
 pub mod token;
-pub mod payload;
\ No newline at end of file
+pub mod span;
+pub mod error;
+pub mod source;
+pub mod symbol;
+pub mod limits;
+pub mod messages;
+pub mod edit;
+pub mod options;
+pub mod line_state;
+pub mod ast;
@@ -0,0 +1,138 @@
+use std::fmt;
+
+use crate::data::limits::Limits;
+
+// how a Scanner reads its source: the hard limits, plus switches for tools
+// and dialects. start from the defaults and chain what differs, e.g.
+//   ScannerOptions::new().keep_comments(true).max_tokens(10_000)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScannerOptions {
+    pub limits: Limits,
+    pub keep_comments: bool, // emit comments as COMMENT tokens, for formatters
+    pub allow_non_ascii: bool, // off reports any non-ascii char, even in strings
+}
+
+impl Default for ScannerOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits::default(),
+            keep_comments: false,
+            allow_non_ascii: true,
+        }
+    }
+}
+
+impl ScannerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn max_tokens(mut self, max: usize) -> Self {
+        self.limits.max_tokens = max;
+        self
+    }
+
+    pub fn max_string_len(mut self, max: usize) -> Self {
+        self.limits.max_string_len = max;
+        self
+    }
+
+    pub fn max_number_len(mut self, max: usize) -> Self {
+        self.limits.max_number_len = max;
+        self
+    }
+
+    pub fn max_nesting_depth(mut self, max: usize) -> Self {
+        self.limits.max_nesting_depth = max;
+        self
+    }
+
+    pub fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    pub fn allow_non_ascii(mut self, allow: bool) -> Self {
+        self.allow_non_ascii = allow;
+        self
+    }
+}
+
+// how a Parser reads its tokens, built the same way as ScannerOptions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub limits: Limits,
+    pub lang: Lang,
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn lang(mut self, lang: Lang) -> Self {
+        self.lang = lang;
+        self
+    }
+}
+
+// which lox a program is written in: the book's, or with the syntax loxrs
+// adds on top. extensions are parsed either way, classic just reports them.
+// lexical ones the parser can't see, like nested block comments and hex
+// numbers, are always accepted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    Classic,
+    #[default]
+    Extended,
+}
+
+impl Lang {
+    // the name --lang takes
+    pub fn from_name(name: &str) -> Option<Lang> {
+        match name {
+            "lox-classic" => Some(Lang::Classic),
+            "loxrs-extended" => Some(Lang::Extended),
+            _ => None,
+        }
+    }
+
+    pub fn allows(self, _extension: Extension) -> bool {
+        self == Lang::Extended
+    }
+}
+
+// syntax that isn't in the book
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extension {
+    Comma,
+    Conditional,
+    Interpolation,
+    RawStrings,
+    Remainder,
+    Bitwise,
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Extension::Comma => "the comma operator",
+            Extension::Conditional => "conditional expressions",
+            Extension::Interpolation => "string interpolation",
+            Extension::RawStrings => "raw strings",
+            Extension::Remainder => "the % operator",
+            Extension::Bitwise => "bitwise and shift operators",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,57 @@
+use crate::data::span::Span;
+
+// owns every piece of source the pipeline has seen (script files, imports,
+// one virtual buffer per REPL line) so spans can say which one they came from
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileId(u32);
+
+struct SourceFile {
+    name: String,
+    text: String,
+}
+
+#[derive(Default)]
+pub struct SourceDatabase {
+    files: Vec<SourceFile>,
+}
+
+impl SourceDatabase {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: String, text: String) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile { name, text });
+        id
+    }
+
+    pub fn name(&self, id: FileId) -> &str {
+        &self.file(id).name
+    }
+
+    pub fn text(&self, id: FileId) -> &str {
+        &self.file(id).text
+    }
+
+    // the source a span covers, as written: 0x1F stays 0x1F, where a
+    // token's lexeme would render the value back as 31
+    pub fn slice(&self, span: Span) -> &str {
+        &self.text(span.file)[span.start..span.end]
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    fn file(&self, id: FileId) -> &SourceFile {
+        self.files
+            .get(id.0 as usize)
+            .expect("file id from another source database")
+    }
+}
@@ -0,0 +1,76 @@
+use crate::data::source::FileId;
+
+// a region of source text. offsets are byte offsets into the file, with
+// the line/column of `start` kept alongside for reporting. columns count
+// chars, not bytes, so they match what an editor shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize, // exclusive
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: usize, end: usize, line: u32, column: u32) -> Self {
+        Self {
+            file,
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    // smallest span covering both, positioned at whichever starts first.
+    // both spans are expected to be in the same file
+    pub fn merge(self, other: Span) -> Span {
+        let first = if other.start < self.start { other } else { self };
+        Span {
+            end: self.end.max(other.end),
+            ..first
+        }
+    }
+
+    // grow in place to also cover other
+    pub fn extend(&mut self, other: Span) {
+        *self = self.merge(other);
+    }
+
+    // move by an edit made somewhere before the span, on an earlier line
+    pub fn shift(&mut self, bytes: isize, lines: isize) {
+        self.start = self.start.wrapping_add_signed(bytes);
+        self.end = self.end.wrapping_add_signed(bytes);
+        self.line = (self.line as isize + lines) as u32;
+    }
+}
+
+// any value tagged with the source it came from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned::new(f(self.node), self.span)
+    }
+
+    pub fn as_ref(&self) -> Spanned<&T> {
+        Spanned::new(&self.node, self.span)
+    }
+}
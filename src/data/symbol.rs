@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// an interned identifier. names are interned once, in the scanner, and every
+// later phase compares and hashes the u32 instead of the string. the name
+// itself lives in the Interner the scanner was given
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+// the names behind symbols. whoever drives the scanner owns one, next to
+// the sources it serves, and hands it to every scan whose symbols should
+// agree. the names are freed when it's dropped, so a tool can start a
+// fresh one for each document, or a repl keep one for the session
+#[derive(Default)]
+pub struct Interner {
+    ids: HashMap<Box<str>, Symbol>,
+    names: Vec<Box<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&sym) = self.ids.get(name) {
+            return sym;
+        }
+        let sym = Symbol(self.names.len() as u32);
+        self.names.push(name.into());
+        self.ids.insert(name.into(), sym);
+        sym
+    }
+
+    pub fn resolve(&self, sym: Symbol) -> &str {
+        self.names
+            .get(sym.0 as usize)
+            .expect("symbol from another interner")
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+// the name needs the interner, see Interner::resolve
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
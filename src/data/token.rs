@@ -1,15 +1,85 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::data::source::SourceDatabase;
+use crate::data::span::Span;
+use crate::data::symbol::Interner;
 use crate::data::types::TokenType;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Token {
     tt: TokenType,
-    lexeme: String,
-    line: i16,
+    span: Span,
 }
 
 impl Token {
-    pub fn new(tt: TokenType, lexeme: String, line: i16) -> Self {
-        Self { tt, lexeme, line }
+    pub fn new(tt: TokenType, span: Span) -> Self {
+        Self { tt, span }
+    }
+
+    pub fn tt(&self) -> &TokenType {
+        &self.tt
+    }
+
+    pub fn lexeme<'a>(&'a self, names: &'a Interner) -> Cow<'a, str> {
+        self.tt.lexeme(names)
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn line(&self) -> u32 {
+        self.span.line
+    }
+
+    pub fn column(&self) -> u32 {
+        self.span.column
+    }
+
+    pub fn shift(&mut self, bytes: isize, lines: isize) {
+        self.span.shift(bytes, lines);
+    }
+
+    // for --tokens, see Written
+    pub fn display_with<'a>(&'a self, db: &'a SourceDatabase) -> Written<'a> {
+        Written { token: self, db }
+    }
+}
+
+// LEFT_PAREN '(' [1:5], with the lexeme as written: the source is the only
+// place that still says 0x1F for a number worth 31
+pub struct Written<'a> {
+    token: &'a Token,
+    db: &'a SourceDatabase,
+}
+
+impl fmt::Display for Written<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let token = self.token;
+        write!(
+            f,
+            "{} '{}' [{}:{}]",
+            token.tt,
+            self.db.slice(token.span),
+            token.line(),
+            token.column()
+        )
+    }
+}
+
+// compact form so a whole token stream fits on a line: LEFT_PAREN@1:5, STRING("hi")@1:6
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.tt {
+            TokenType::Identifier(name) => write!(f, "{}({:?})", self.tt, name)?,
+            TokenType::String(s)
+            | TokenType::StringPart(s)
+            | TokenType::RawString(s)
+            | TokenType::Comment(s) => write!(f, "{}({:?})", self.tt, s)?,
+            TokenType::Number(n) => write!(f, "{}({})", self.tt, n)?,
+            _ => write!(f, "{}", self.tt)?,
+        }
+        write!(f, "@{}:{}", self.line(), self.column())
     }
 }
-    
\ No newline at end of file
@@ -1,4 +1,9 @@
-#[derive(Clone)]
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::data::symbol::{Interner, Symbol};
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum TokenType {
     // single character
     LeftParen,
@@ -12,6 +17,13 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Question,
+    Colon,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // one or two characters
     Bang,
@@ -22,11 +34,21 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
 
-    // literals
-    Identifier,
+    // literals, carrying their scanned values
+    Identifier(Symbol),
     String(String),
-    Number,
+    Number(f64),
+    RawString(String), // `...`, taken verbatim
+    Comment(String),   // the whole comment, only kept when asked for
+
+    // string interpolation: "a ${b} c" scans as STRING_PART INTERP_START
+    // IDENTIFIER INTERP_END STRING_PART, see Scanner::string
+    StringPart(String),
+    InterpStart,
+    InterpEnd,
 
     // keywords
     And,
@@ -48,3 +70,162 @@ pub enum TokenType {
 
     End // eof
 }
+
+impl TokenType {
+    // source text for the token; literal payloads are rendered back from
+    // their values, and names looked up in the interner they came from
+    pub fn lexeme<'a>(&'a self, names: &'a Interner) -> Cow<'a, str> {
+        match self {
+            TokenType::Identifier(name) => Cow::Borrowed(names.resolve(*name)),
+            TokenType::String(s) => Cow::Owned(format!("\"{}\"", escape(s))),
+            TokenType::Number(n) => Cow::Owned(n.to_string()),
+            TokenType::StringPart(s) => escape(s),
+            TokenType::RawString(s) => Cow::Owned(format!("`{}`", s)),
+            TokenType::Comment(s) => Cow::Borrowed(s),
+            _ => Cow::Borrowed(self.fixed_lexeme().unwrap_or_default()),
+        }
+    }
+
+    // the text of a token that's always spelled the same, none for
+    // identifiers and literals
+    pub fn fixed_lexeme(&self) -> Option<&'static str> {
+        let text = match self {
+            TokenType::Identifier(_)
+            | TokenType::String(_)
+            | TokenType::Number(_)
+            | TokenType::StringPart(_)
+            | TokenType::RawString(_)
+            | TokenType::Comment(_) => return None,
+            TokenType::LeftParen => "(",
+            TokenType::RightParen => ")",
+            TokenType::LeftBrace => "{",
+            TokenType::RightBrace => "}",
+            TokenType::Comma => ",",
+            TokenType::Dot => ".",
+            TokenType::Minus => "-",
+            TokenType::Plus => "+",
+            TokenType::Semicolon => ";",
+            TokenType::Slash => "/",
+            TokenType::Star => "*",
+            TokenType::Question => "?",
+            TokenType::Colon => ":",
+            TokenType::Percent => "%",
+            TokenType::Ampersand => "&",
+            TokenType::Pipe => "|",
+            TokenType::Caret => "^",
+            TokenType::Tilde => "~",
+            TokenType::InterpStart => "${",
+            TokenType::InterpEnd => "}",
+            TokenType::Bang => "!",
+            TokenType::BangEqual => "!=",
+            TokenType::Equal => "=",
+            TokenType::EqualEqual => "==",
+            TokenType::Greater => ">",
+            TokenType::GreaterEqual => ">=",
+            TokenType::Less => "<",
+            TokenType::LessEqual => "<=",
+            TokenType::LessLess => "<<",
+            TokenType::GreaterGreater => ">>",
+            TokenType::And => "and",
+            TokenType::Class => "class",
+            TokenType::Else => "else",
+            TokenType::False => "false",
+            TokenType::Fun => "fun",
+            TokenType::For => "for",
+            TokenType::If => "if",
+            TokenType::Nil => "nil",
+            TokenType::Or => "or",
+            TokenType::Print => "print",
+            TokenType::Return => "return",
+            TokenType::Super => "super",
+            TokenType::This => "this",
+            TokenType::True => "true",
+            TokenType::Var => "var",
+            TokenType::While => "while",
+            TokenType::End => "",
+        };
+        Some(text)
+    }
+}
+
+// re-escapes a string payload so its lexeme scans back to the same value.
+// newlines stay literal, lox strings are allowed to span lines
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['"', '\\', '\t', '\r']) && !s.contains("${") {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() + 2);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TokenType::LeftParen => "LEFT_PAREN",
+            TokenType::RightParen => "RIGHT_PAREN",
+            TokenType::LeftBrace => "LEFT_BRACE",
+            TokenType::RightBrace => "RIGHT_BRACE",
+            TokenType::Comma => "COMMA",
+            TokenType::Dot => "DOT",
+            TokenType::Minus => "MINUS",
+            TokenType::Plus => "PLUS",
+            TokenType::Semicolon => "SEMICOLON",
+            TokenType::Slash => "SLASH",
+            TokenType::Star => "STAR",
+            TokenType::Question => "QUESTION",
+            TokenType::Colon => "COLON",
+            TokenType::Percent => "PERCENT",
+            TokenType::Ampersand => "AMPERSAND",
+            TokenType::Pipe => "PIPE",
+            TokenType::Caret => "CARET",
+            TokenType::Tilde => "TILDE",
+            TokenType::Bang => "BANG",
+            TokenType::BangEqual => "BANG_EQUAL",
+            TokenType::Equal => "EQUAL",
+            TokenType::EqualEqual => "EQUAL_EQUAL",
+            TokenType::Greater => "GREATER",
+            TokenType::GreaterEqual => "GREATER_EQUAL",
+            TokenType::Less => "LESS",
+            TokenType::LessEqual => "LESS_EQUAL",
+            TokenType::LessLess => "LESS_LESS",
+            TokenType::GreaterGreater => "GREATER_GREATER",
+            TokenType::Identifier(_) => "IDENTIFIER",
+            TokenType::String(_) => "STRING",
+            TokenType::Number(_) => "NUMBER",
+            TokenType::RawString(_) => "RAW_STRING",
+            TokenType::Comment(_) => "COMMENT",
+            TokenType::StringPart(_) => "STRING_PART",
+            TokenType::InterpStart => "INTERP_START",
+            TokenType::InterpEnd => "INTERP_END",
+            TokenType::And => "AND",
+            TokenType::Class => "CLASS",
+            TokenType::Else => "ELSE",
+            TokenType::False => "FALSE",
+            TokenType::Fun => "FUN",
+            TokenType::For => "FOR",
+            TokenType::If => "IF",
+            TokenType::Nil => "NIL",
+            TokenType::Or => "OR",
+            TokenType::Print => "PRINT",
+            TokenType::Return => "RETURN",
+            TokenType::Super => "SUPER",
+            TokenType::This => "THIS",
+            TokenType::True => "TRUE",
+            TokenType::Var => "VAR",
+            TokenType::While => "WHILE",
+            TokenType::End => "EOF",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -1,55 +1,653 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::env;
+use std::fmt::Display;
 use std::fs;
-use std::io::Error;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::process;
+use std::time::{Duration, Instant};
 
-mod backend;
-mod data;
+use loxrs::backend::parser::Parser;
+use loxrs::backend::printer::AstPrinter;
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::ast::{Expr, ExprVisitor, Function, Literal, Stmt, StmtVisitor};
+use loxrs::data::error::LoxError;
+use loxrs::data::line_state::LineState;
+use loxrs::data::messages::{render, Message};
+use loxrs::data::options::{Lang, ParserOptions};
+use loxrs::data::source::{FileId, SourceDatabase};
+use loxrs::data::span::Span;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+use loxrs::data::types::TokenType;
+
+// command line switches, threaded through to run(). a program that parses
+// cleanly prints nothing unless one of them asks for it
+#[derive(Default)]
+struct Config {
+    tokens: bool,    // dump the token stream, one token per line
+    print_ast: bool, // print the syntax tree as s-expressions
+    debug_ast: bool, // print the syntax tree's Debug form, spans and all
+    stats: bool,     // print pipeline counters after each run
+    lang: Lang,      // the dialect programs are parsed as
+}
+
+// what the pipeline did during one run, reported by --stats
+struct Stats {
+    bytes: usize,
+    lines: usize,
+    tokens: usize,
+    scan_time: Duration,
+    nodes: Option<usize>, // none when --tokens stops the run before parsing
+    parse_time: Option<Duration>,
+}
+
+impl Stats {
+    fn print(&self) {
+        eprintln!("-- stats --");
+        eprintln!("source bytes    {:>10}", self.bytes);
+        eprintln!("source lines    {:>10}", self.lines);
+        eprintln!("tokens scanned  {:>10}", self.tokens);
+        eprintln!("scan time       {:>10.3?}", self.scan_time);
+        if let Some(nodes) = self.nodes {
+            eprintln!("ast nodes       {:>10}", nodes);
+        }
+        if let Some(parse_time) = self.parse_time {
+            eprintln!("parse time      {:>10.3?}", parse_time);
+        }
+    }
+}
+
+// counts the nodes in a syntax tree for --stats: every expression and
+// statement, with each method counted as the function declaration it is
+struct NodeCounter;
+
+impl NodeCounter {
+    fn stmts(&mut self, statements: &[Stmt]) -> usize {
+        statements.iter().map(|s| s.accept(self)).sum()
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> usize {
+        exprs.iter().map(|e| e.accept(self)).sum()
+    }
+
+    fn function(&mut self, function: &Function) -> usize {
+        1 + self.stmts(&function.body)
+    }
+}
+
+impl StmtVisitor<usize> for NodeCounter {
+    fn visit_expression(&mut self, _: Span, expr: &Expr) -> usize {
+        1 + expr.accept(self)
+    }
+
+    fn visit_print(&mut self, _: Span, expr: &Expr) -> usize {
+        1 + expr.accept(self)
+    }
+
+    fn visit_var(&mut self, _: Span, _: &Token, initializer: Option<&Expr>) -> usize {
+        1 + initializer.map_or(0, |init| init.accept(self))
+    }
+
+    fn visit_block(&mut self, _: Span, statements: &[Stmt]) -> usize {
+        1 + self.stmts(statements)
+    }
+
+    fn visit_class(
+        &mut self,
+        _: Span,
+        _: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> usize {
+        let methods: usize = methods.iter().map(|m| self.function(m)).sum();
+        1 + superclass.map_or(0, |s| s.accept(self)) + methods
+    }
+
+    fn visit_function(&mut self, _: Span, function: &Function) -> usize {
+        self.function(function)
+    }
+
+    fn visit_if(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> usize {
+        let branches = then_branch.accept(self) + else_branch.map_or(0, |s| s.accept(self));
+        1 + condition.accept(self) + branches
+    }
+
+    fn visit_return(&mut self, _: Span, _: &Token, value: Option<&Expr>) -> usize {
+        1 + value.map_or(0, |v| v.accept(self))
+    }
+
+    fn visit_while(&mut self, _: Span, condition: &Expr, body: &Stmt) -> usize {
+        1 + condition.accept(self) + body.accept(self)
+    }
+}
+
+impl ExprVisitor<usize> for NodeCounter {
+    fn visit_binary(&mut self, _: Span, left: &Expr, _: &Token, right: &Expr) -> usize {
+        1 + left.accept(self) + right.accept(self)
+    }
+
+    fn visit_unary(&mut self, _: Span, _: &Token, right: &Expr) -> usize {
+        1 + right.accept(self)
+    }
+
+    fn visit_logical(&mut self, span: Span, left: &Expr, op: &Token, right: &Expr) -> usize {
+        self.visit_binary(span, left, op, right)
+    }
+
+    fn visit_conditional(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> usize {
+        1 + condition.accept(self) + then_branch.accept(self) + else_branch.accept(self)
+    }
+
+    fn visit_call(&mut self, _: Span, callee: &Expr, _: &Token, arguments: &[Expr]) -> usize {
+        1 + callee.accept(self) + self.exprs(arguments)
+    }
+
+    fn visit_get(&mut self, _: Span, object: &Expr, _: &Token) -> usize {
+        1 + object.accept(self)
+    }
+
+    fn visit_set(&mut self, _: Span, object: &Expr, _: &Token, value: &Expr) -> usize {
+        1 + object.accept(self) + value.accept(self)
+    }
+
+    fn visit_this(&mut self, _: Span, _: &Token) -> usize {
+        1
+    }
+
+    fn visit_super(&mut self, _: Span, _: &Token, _: &Token) -> usize {
+        1
+    }
+
+    fn visit_grouping(&mut self, _: Span, inner: &Expr) -> usize {
+        1 + inner.accept(self)
+    }
+
+    fn visit_literal(&mut self, _: Span, _: &Literal) -> usize {
+        1
+    }
+
+    fn visit_variable(&mut self, _: Span, _: &Token) -> usize {
+        1
+    }
+
+    fn visit_assign(&mut self, _: Span, _: &Token, value: &Expr) -> usize {
+        1 + value.accept(self)
+    }
+
+    fn visit_interpolation(&mut self, _: Span, parts: &[Expr]) -> usize {
+        1 + self.exprs(parts)
+    }
+}
+
+thread_local! {
+    // rust source location of the last panic, filled in by the panic hook
+    static PANIC_SITE: RefCell<Option<String>> = const { RefCell::new(None) };
+    // whether the terminal was asked for bracketed paste, see PasteMode
+    static PASTE_MODE: Cell<bool> = const { Cell::new(false) };
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        panic!("Usage: loxrs [script]");
-    } else if std::env::args().count() == 1 {
-        run_file(&args[0]);
+    panic::set_hook(Box::new(record_panic_site));
+    if let Err(msg) = guarded(start) {
+        // process::exit skips destructors, so a repl's terminal is put back here
+        paste_mode_off();
+        internal_error(None, "driver", &msg, None);
+        process::exit(70);
+    }
+}
+
+fn start() {
+    let mut config = Config::default();
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--tokens" => config.tokens = true,
+            "--print-ast" => config.print_ast = true,
+            "--debug-ast" => config.debug_ast = true,
+            "--stats" => config.stats = true,
+            flag if flag.starts_with("--lang=") => {
+                match flag.strip_prefix("--lang=").and_then(Lang::from_name) {
+                    Some(lang) => config.lang = lang,
+                    None => usage(),
+                }
+            }
+            flag if flag.starts_with("--") => usage(),
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.len() > 1 {
+        usage();
+    } else if let Some(path) = paths.first() {
+        run_file(path, &config);
     } else {
-        run_prompt();
+        run_prompt(&config);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", render(Message::Usage, &[]));
+    process::exit(64);
+}
+
+fn run_file(path: &str, config: &Config) {
+    let src = read_source(path).unwrap_or_else(|(msg, code)| {
+        eprintln!("{}", render(Message::Error, &[("message", &msg)]));
+        process::exit(code);
+    });
+    let mut db = SourceDatabase::new();
+    let mut names = Interner::new();
+    let file = db.add(path.to_string(), src);
+    match run(&db, &mut names, file, config) {
+        Ok(()) => {}
+        Err(Failure::Errors(errors)) => {
+            report_errors(&db, &errors);
+            process::exit(65);
+        }
+        Err(Failure::Internal) => process::exit(70),
     }
 }
 
-fn run_file(path: &String) {
-    let src = fs::read_to_string(path).expect("Unable to read file at the given path.");
-    run(src).unwrap_or_else(|err| {
-        eprintln!("omg!!! {}", err);
+// a final line without a newline still counts, but there's no empty line
+// after a trailing one
+fn line_count(text: &str) -> usize {
+    let newlines = text.bytes().filter(|&b| b == b'\n').count();
+    newlines + usize::from(!text.is_empty() && !text.ends_with('\n'))
+}
+
+// reads a script, describing what went wrong (and the exit code to use) if it can't
+fn read_source(path: &str) -> Result<String, (String, i32)> {
+    let bytes = fs::read(path).map_err(|err| {
+        let reason = io_reason(&err);
+        let msg = render(Message::CantRead, &[("path", &path), ("reason", &reason)]);
+        (msg, 66)
+    })?;
+
+    String::from_utf8(bytes).map_err(|err| {
+        let valid = &err.as_bytes()[..err.utf8_error().valid_up_to()];
+        let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+        let column = valid.iter().rev().take_while(|&&b| b != b'\n').count() + 1;
+        let args: [(&str, &dyn Display); 3] =
+            [("path", &path), ("line", &line), ("column", &column)];
+        let msg = render(Message::InvalidUtf8, &args);
+        (msg, 65)
     })
 }
 
-fn run_prompt() {
+fn io_reason(err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => render(Message::NoSuchFile, &[]),
+        io::ErrorKind::PermissionDenied => render(Message::PermissionDenied, &[]),
+        io::ErrorKind::IsADirectory => render(Message::IsADirectory, &[]),
+        _ => err.to_string(),
+    }
+}
+
+// terminals asked for bracketed paste wrap whatever is pasted in these, so
+// a pasted block can be run as one program rather than line by line
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+fn run_prompt(config: &Config) {
     let stdin = io::stdin();
+    let _paste = (stdin.is_terminal() && io::stdout().is_terminal()).then(PasteMode::on);
+    let mut session = Session::default();
     for line in stdin.lock().lines() {
         match line {
-            Ok(line) => run(line).unwrap_or_else(|err| {
-                eprintln!("omg!!! {}", err);
-            }),
+            Ok(line) => session.input(&line, config),
             Err(_e) => break,
         }
     }
+    session.flush(config);
+}
+
+// bracketed paste, on for as long as this lives. the terminal keeps the
+// mode after we exit, so it's switched off however the repl ends
+struct PasteMode;
+
+impl PasteMode {
+    fn on() -> Self {
+        print!("\x1b[?2004h");
+        let _ = io::stdout().flush();
+        PASTE_MODE.with(|on| on.set(true));
+        PasteMode
+    }
+}
+
+impl Drop for PasteMode {
+    fn drop(&mut self) {
+        paste_mode_off();
+    }
+}
+
+fn paste_mode_off() {
+    if PASTE_MODE.with(|on| on.replace(false)) {
+        print!("\x1b[?2004l");
+        let _ = io::stdout().flush();
+    }
+}
+
+// state that outlives a single repl line
+#[derive(Default)]
+struct Session {
+    db: SourceDatabase,
+    names: Interner,             // for every line of the session, like db
+    recording: Option<fs::File>, // where :record is logging input, if anywhere
+    // lines held back until the program they're part of is finished: while
+    // a paste is still coming, or a bracket, string or comment is left open
+    pending: Vec<String>,
+    state: LineState, // what the pending lines leave open
+    depth: usize,     // brackets the pending lines have left open
+    pasting: bool,
+    replaying: bool, // commands in a log are skipped, so one can't replay itself
+}
+
+impl Session {
+    // a line as the terminal sent it. a paste can start at the beginning of
+    // one and, since the terminal only hands over whole lines, end anywhere
+    // in a later one, with whatever was typed after it. lines are recorded
+    // as they came, paste markers and all, so a replay groups them the same
+    fn input(&mut self, line: &str, config: &Config) {
+        if !self.is_command(line) {
+            self.record(line);
+        }
+        let line = match line.strip_prefix(PASTE_START) {
+            Some(pasted) => {
+                self.pasting = true;
+                pasted
+            }
+            None => line,
+        };
+        match line.split_once(PASTE_END) {
+            Some((pasted, typed)) => {
+                if !pasted.is_empty() {
+                    self.eval(pasted, config);
+                }
+                self.pasting = false;
+                self.flush(config);
+                if !typed.is_empty() {
+                    self.eval(typed, config);
+                }
+            }
+            None => self.eval(line, config),
+        }
+    }
+
+    // a blank line runs what's pending even if it isn't finished, so a
+    // stray bracket can't keep swallowing input
+    fn eval(&mut self, line: &str, config: &Config) {
+        if self.is_command(line) {
+            if !self.replaying {
+                self.command(&line[1..], config);
+            }
+            return;
+        }
+        if line.trim().is_empty() && !self.pasting {
+            self.flush(config);
+            return;
+        }
+
+        // only the brackets matter here, so the names go in a scratch table
+        let (tokens, state) = Scanner::tokenize_line(line, &self.state, &mut Interner::new());
+        for token in &tokens {
+            match token.tt() {
+                TokenType::LeftBrace | TokenType::LeftParen => self.depth += 1,
+                TokenType::RightBrace | TokenType::RightParen => {
+                    self.depth = self.depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+        }
+        self.state = state;
+        self.pending.push(line.to_string());
+        let open = self.state.open.is_some() || !self.state.interpolations.is_empty();
+        if !self.pasting && !open && self.depth == 0 {
+            self.flush(config);
+        }
+    }
+
+    // commands are only recognised when typed on a line of their own
+    fn is_command(&self, line: &str) -> bool {
+        self.pending.is_empty() && !self.pasting && line.starts_with(':')
+    }
+
+    fn record(&mut self, line: &str) {
+        if let Some(log) = &mut self.recording {
+            if let Err(err) = writeln!(log, "{}", line) {
+                eprintln!("{}", render(Message::RecordingStopped, &[("reason", &err)]));
+                self.recording = None;
+            }
+        }
+    }
+
+    // runs the pending lines as one program, in a virtual buffer of its own
+    fn flush(&mut self, config: &Config) {
+        self.state = LineState::default();
+        self.depth = 0;
+        if self.pending.is_empty() {
+            return;
+        }
+        let src = self.pending.join("\n");
+        self.pending.clear();
+        let file = self.db.add(format!("<repl:{}>", self.db.len() + 1), src);
+        if let Err(Failure::Errors(errors)) = run(&self.db, &mut self.names, file, config) {
+            report_errors(&self.db, &errors);
+        }
+    }
+
+    // :record <path> logs every line typed from now on, :stop ends that, and
+    // :replay <path> runs a log's lines as if they were typed again, except
+    // for any commands in it
+    fn command(&mut self, command: &str, config: &Config) {
+        let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+        let path = arg.trim();
+        match name {
+            "record" if !path.is_empty() => match fs::File::create(path) {
+                Ok(log) => self.recording = Some(log),
+                Err(err) => {
+                    let reason = io_reason(&err);
+                    let msg = render(Message::CantWrite, &[("path", &path), ("reason", &reason)]);
+                    eprintln!("{}", render(Message::Error, &[("message", &msg)]));
+                }
+            },
+            "stop" => self.recording = None,
+            "replay" if !path.is_empty() => match read_source(path) {
+                Ok(src) => {
+                    self.replaying = true;
+                    for line in src.lines() {
+                        self.input(line, config);
+                    }
+                    self.replaying = false;
+                    self.pasting = false;
+                    self.flush(config);
+                }
+                Err((msg, _)) => eprintln!("{}", render(Message::Error, &[("message", &msg)])),
+            },
+            _ => eprintln!("{}", render(Message::ReplCommands, &[("command", &name)])),
+        }
+    }
+}
+
+// why a run stopped short
+enum Failure {
+    Errors(Vec<LoxError>), // in the program, and not yet reported
+    Internal,              // a bug in loxrs, already reported
 }
 
-fn run(source: String) -> Result<(), Error> {
-    let tokens: Vec<&str> = source.split(' ').collect();
-    for token in tokens {
-        println!("{}", token);
+// like jlox's hadError, any error stops the run once the phase that found it
+// has reported everything it could
+
+fn run(
+    db: &SourceDatabase,
+    names: &mut Interner,
+    file: FileId,
+    config: &Config,
+) -> Result<(), Failure> {
+    let mut scanner = Scanner::new(file, db.text(file).to_string(), names);
+    let scan_start = Instant::now();
+    let scan = guarded(|| {
+        forced_panic("scanner");
+        scanner.scan_tokens()
+    });
+    let tokens = match scan {
+        Ok((tokens, errors)) if errors.is_empty() => tokens,
+        Ok((_, errors)) => {
+            return Err(Failure::Errors(
+                errors.into_iter().map(LoxError::from).collect(),
+            ))
+        }
+        Err(msg) => {
+            internal_error(Some(scanner.line()), "scanner", &msg, Some(scanner.state()));
+            return Err(Failure::Internal);
+        }
+    };
+    let scan_time = scan_start.elapsed();
+    let scanned = tokens.len();
+    let (mut nodes, mut parse_time) = (None, None);
+    if config.tokens {
+        for token in &tokens {
+            println!("{}", token.display_with(db));
+        }
+    } else {
+        let mut parser = Parser::with_options(tokens, ParserOptions::new().lang(config.lang));
+        let parse_start = Instant::now();
+        let parse = guarded(|| {
+            forced_panic("parser");
+            parser.parse()
+        });
+        match parse {
+            Ok((statements, errors)) if errors.is_empty() => {
+                parse_time = Some(parse_start.elapsed());
+                nodes = Some(NodeCounter.stmts(&statements));
+                for stmt in &statements {
+                    if config.print_ast {
+                        println!("{}", AstPrinter::new(names).print_stmt(stmt));
+                    }
+                    if config.debug_ast {
+                        println!("{:?}", stmt);
+                    }
+                }
+            }
+            Ok((_, errors)) => {
+                return Err(Failure::Errors(
+                    errors.into_iter().map(LoxError::from).collect(),
+                ))
+            }
+            Err(msg) => {
+                internal_error(Some(parser.line()), "parser", &msg, Some(parser.state()));
+                return Err(Failure::Internal);
+            }
+        }
+    }
+    if config.stats {
+        let stats = Stats {
+            bytes: db.text(file).len(),
+            lines: line_count(db.text(file)),
+            tokens: scanned,
+            scan_time,
+            nodes,
+            parse_time,
+        };
+        stats.print();
     }
     Ok(())
 }
 
 // error handling methods
 
-fn error(line: i16, msg: String) {
-    report(line, "".to_string(), msg);
+fn error(line: u32, msg: String) {
+    report(line.to_string(), "".to_string(), msg);
+}
+
+// position is a line, or line:column when there's a span to take it from
+fn report(position: String, location: String, msg: String) {
+    let args: [(&str, &dyn Display); 3] = [
+        ("position", &position),
+        ("location", &location),
+        ("message", &msg),
+    ];
+    eprintln!("{}", render(Message::ErrorAt, &args));
 }
 
-fn report(line: i16, location: String, msg: String) {
-    eprintln!("[line {}] Error {}: {}", line, location, msg);
+fn report_errors(db: &SourceDatabase, errors: &[LoxError]) {
+    for err in errors {
+        let span = err.span();
+        let mut location = render(Message::InFile, &[("file", &db.name(span.file))]);
+        if let LoxError::Parse(err) = err {
+            location += &match err.token.tt() {
+                TokenType::End => render(Message::AtEnd, &[]),
+                _ => render(Message::AtToken, &[("lexeme", &db.slice(err.token.span()))]),
+            };
+        }
+        let position = render(
+            Message::Position,
+            &[("line", &span.line), ("column", &span.column)],
+        );
+        report(position, location, err.to_string());
+    }
+}
+
+// internal errors: a panic anywhere in the pipeline is our bug, not the user's,
+// so report it as one instead of dumping a rust backtrace on them
+
+fn guarded<T>(phase: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(phase)).map_err(panic_message)
+}
+
+// LOXRS_FORCE_PANIC=<phase> makes that phase panic, so tests can reach the
+// internal error path. debug builds only
+fn forced_panic(phase: &str) {
+    if cfg!(debug_assertions) && env::var("LOXRS_FORCE_PANIC").is_ok_and(|p| p == phase) {
+        panic!("forced by LOXRS_FORCE_PANIC");
+    }
+}
+
+fn record_panic_site(info: &PanicHookInfo) {
+    let site = info.location().map(|loc| loc.to_string());
+    PANIC_SITE.with(|s| *s.borrow_mut() = site);
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn internal_error(line: Option<u32>, phase: &str, msg: &str, state: Option<String>) {
+    let msg = render(
+        Message::InternalError,
+        &[("phase", &phase), ("message", &msg)],
+    );
+    match line {
+        Some(line) => error(line, msg),
+        None => eprintln!("{}", render(Message::Error, &[("message", &msg)])),
+    }
+    eprintln!("{}", render(Message::ReportBug, &[]));
+    if let Some(site) = PANIC_SITE.with(|s| s.borrow_mut().take()) {
+        eprintln!("{}", render(Message::PanickedAt, &[("site", &site)]));
+    }
+    if let Some(state) = state {
+        eprintln!(
+            "{}",
+            render(Message::PhaseState, &[("phase", &phase), ("state", &state)])
+        );
+    }
 }
@@ -0,0 +1,40 @@
+// the semantics of % and the bitwise operators, ahead of an interpreter
+
+use loxrs::backend::arithmetic::*;
+
+#[test]
+fn remainder_keeps_the_sign_of_the_left_operand() {
+    assert_eq!(remainder(7.0, 3.0), 1.0);
+    assert_eq!(remainder(-7.0, 3.0), -1.0);
+    assert_eq!(remainder(7.0, -3.0), 1.0);
+    assert_eq!(remainder(5.5, 2.0), 1.5);
+    assert!(remainder(1.0, 0.0).is_nan());
+}
+
+#[test]
+fn bitwise_operators_truncate_to_integers() {
+    assert_eq!(bit_and(6.0, 3.0), 2.0);
+    assert_eq!(bit_or(6.0, 3.0), 7.0);
+    assert_eq!(bit_xor(6.0, 3.0), 5.0);
+    assert_eq!(bit_not(0.0), -1.0);
+    assert_eq!(bit_not(5.0), -6.0);
+
+    // toward zero, saturating, and NaN as 0
+    assert_eq!(bit_and(7.9, 7.0), 7.0);
+    assert_eq!(bit_or(-2.5, 0.0), -2.0);
+    assert_eq!(bit_or(f64::INFINITY, 0.0), i64::MAX as f64);
+    assert_eq!(bit_or(f64::NAN, 1.0), 1.0);
+}
+
+#[test]
+fn shifts() {
+    assert_eq!(shift_left(1.0, 10.0), 1024.0);
+    assert_eq!(shift_right(1024.0, 3.0), 128.0);
+    assert_eq!(shift_right(-8.0, 1.0), -4.0);
+    assert_eq!(shift_right(-1.0, 63.0), -1.0);
+
+    // counts are taken mod 64
+    assert_eq!(shift_left(1.0, 64.0), 1.0);
+    assert_eq!(shift_left(1.0, 65.0), 2.0);
+    assert_eq!(shift_left(1.0, -1.0), i64::MIN as f64);
+}
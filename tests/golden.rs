@@ -0,0 +1,78 @@
+// snapshot tests: every tests/golden/*.lox is run through the binary and its
+// output compared with the .tokens file next to it, and likewise every
+// tests/golden/ast/*.lox with its .ast file. when a change in output
+// is intended, rerun with LOXRS_BLESS=1 to rewrite the snapshots and review
+// the diff like any other change
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn samples(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut samples: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("golden sample directories should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    samples.sort();
+    samples
+}
+
+// stdout, then stderr if there was any. paths are relative to the crate root
+// so the snapshots don't depend on where the checkout lives
+fn render(sample: &Path, flag: &str) -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let relative = sample.strip_prefix(root).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_loxrs"))
+        .current_dir(root)
+        .arg(flag)
+        .arg(relative)
+        .output()
+        .expect("failed to run loxrs");
+
+    let mut rendered = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    if !stderr.is_empty() {
+        rendered.push_str("-- stderr --\n");
+        rendered.push_str(&stderr);
+    }
+    rendered
+}
+
+fn check(dir: &str, flag: &str, extension: &str) {
+    let bless = env::var_os("LOXRS_BLESS").is_some();
+    let mut mismatched = Vec::new();
+
+    for sample in samples(dir) {
+        let actual = render(&sample, flag);
+        let snapshot = sample.with_extension(extension);
+        if bless {
+            fs::write(&snapshot, &actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&snapshot).unwrap_or_default();
+        if actual != expected {
+            eprintln!("--- {}\n{}", snapshot.display(), actual);
+            mismatched.push(snapshot);
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "snapshots out of date (rerun with LOXRS_BLESS=1 if intended): {:?}",
+        mismatched
+    );
+}
+
+#[test]
+fn tokens() {
+    check("tests/golden", "--tokens", "tokens");
+}
+
+#[test]
+fn ast() {
+    check("tests/golden/ast", "--print-ast", "ast");
+}
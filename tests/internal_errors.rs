@@ -0,0 +1,24 @@
+// a panic in any phase is reported as a bug in loxrs, with exit code 70
+// (EX_SOFTWARE), rather than a backtrace or a clean exit
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn phase_panics_exit_70() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/ast/precedence.lox");
+    for phase in ["scanner", "parser"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_loxrs"))
+            .env("LOXRS_FORCE_PANIC", phase)
+            .arg(&script)
+            .output()
+            .expect("failed to run loxrs");
+        assert_eq!(output.status.code(), Some(70), "{}", phase);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains(&format!("internal error in {}", phase)),
+            "{}",
+            stderr
+        );
+    }
+}
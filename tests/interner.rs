@@ -0,0 +1,41 @@
+// identifiers are interned into whichever Interner the scanner is given:
+// once per distinct name, with the same symbol every time it's seen
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::{Interner, Symbol};
+use loxrs::data::types::TokenType;
+
+fn symbols(source: &str, names: &mut Interner) -> Vec<Symbol> {
+    let (tokens, _) = Scanner::new(FileId::default(), source.to_string(), names).scan_tokens();
+    tokens
+        .iter()
+        .filter_map(|t| match t.tt() {
+            TokenType::Identifier(sym) => Some(*sym),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn each_name_once() {
+    let mut names = Interner::new();
+    let first = symbols("a + b * a", &mut names);
+    assert_eq!(first[0], first[2]);
+    assert_ne!(first[0], first[1]);
+    assert_eq!(names.len(), 2);
+
+    // a later scan with the same interner agrees on the names it shares
+    let second = symbols("b(c)", &mut names);
+    assert_eq!(second[0], first[1]);
+    assert_eq!(names.len(), 3);
+    let resolved: Vec<_> = second.iter().map(|&s| names.resolve(s)).collect();
+    assert_eq!(resolved, ["b", "c"]);
+
+    // and a fresh one starts empty, owing nothing to the first
+    let mut fresh = Interner::new();
+    assert!(fresh.is_empty());
+    let sym = symbols("c", &mut fresh)[0];
+    assert_eq!(fresh.resolve(sym), "c");
+    assert_eq!(fresh.len(), 1);
+}
@@ -0,0 +1,54 @@
+// the exact spans of lexical errors, which the golden snapshots don't show
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::error::{LexError, LexErrorKind};
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+
+fn errors(source: &str) -> Vec<LexError> {
+    Scanner::new(FileId::default(), source.to_string(), &mut Interner::new())
+        .scan_tokens()
+        .1
+}
+
+// the kind and the source text each error points at
+fn pointed(source: &str) -> Vec<(LexErrorKind, &str)> {
+    errors(source)
+        .into_iter()
+        .map(|err| (err.kind.clone(), &source[err.span.start..err.span.end]))
+        .collect()
+}
+
+#[test]
+fn malformed_numbers() {
+    let empty_hex = LexErrorKind::EmptyRadixLiteral("0x".to_string());
+    assert_eq!(pointed("a = 0x;"), [(empty_hex.clone(), "0x")]);
+    // the message names the prefix alone, though the error covers the separators
+    assert_eq!(pointed("a = 0x_;"), [(empty_hex, "0x_")]);
+    let empty_bin = LexErrorKind::EmptyRadixLiteral("0b".to_string());
+    assert_eq!(pointed("0b__ + 1"), [(empty_bin, "0b__")]);
+    let upper = LexErrorKind::EmptyRadixLiteral("0X".to_string());
+    assert_eq!(pointed("0X_"), [(upper, "0X_")]);
+    assert_eq!(pointed("1e+ 2"), [(LexErrorKind::EmptyExponent, "1e+")]);
+
+    let bad_digit = LexErrorKind::InvalidDigit {
+        digit: '2',
+        radix: 2,
+    };
+    assert_eq!(pointed("0b1021 + 1"), [(bad_digit, "2")]);
+
+    let misplaced = LexErrorKind::MisplacedSeparator;
+    assert_eq!(pointed("1_000__000"), [(misplaced.clone(), "_")]);
+    assert_eq!(errors("x + 1__0")[0].span.column, 6);
+    assert_eq!(
+        pointed("0x_1 1_"),
+        [(misplaced.clone(), "_"), (misplaced, "_")]
+    );
+}
+
+#[test]
+fn one_error_per_malformed_literal() {
+    // recovery swallows the rest of the literal instead of scanning it as
+    // more tokens, so each typo is reported once
+    assert_eq!(errors("0xFGH 0b2 1e").len(), 3);
+}
@@ -0,0 +1,53 @@
+// the catalog is process-wide, so everything that installs one lives in a
+// single test, and the others stay clear of the messages it replaces
+
+use loxrs::data::error::LexErrorKind;
+use loxrs::data::messages::{self, render, Catalog, Message};
+
+struct Pirate;
+
+impl Catalog for Pirate {
+    fn template(&self, msg: Message) -> Option<&str> {
+        match msg {
+            Message::UnterminatedString => Some("Arr, yer string never ends."),
+            Message::CantRead => Some("{reason}, matey: '{path}'"),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn placeholders_and_braces() {
+    let msg = render(Message::NumberTooLong, &[("len", &2000), ("max", &1024)]);
+    assert_eq!(
+        msg,
+        "Number literal is 2000 characters long; the limit is 1024."
+    );
+    let msg = LexErrorKind::UnterminatedInterpolation.to_string();
+    assert_eq!(
+        msg,
+        "Unterminated interpolation; expected '}' to close '${'."
+    );
+    // unknown parameters are left for the reader to notice
+    assert_eq!(render(Message::PanickedAt, &[]), "note: panicked at {site}");
+}
+
+#[test]
+fn installed_catalog_overrides_and_falls_back() {
+    assert!(messages::install(Box::new(Pirate)).is_ok());
+    assert!(messages::install(Box::new(Pirate)).is_err());
+
+    assert_eq!(
+        LexErrorKind::UnterminatedString.to_string(),
+        "Arr, yer string never ends."
+    );
+    let msg = render(
+        Message::CantRead,
+        &[("path", &"x.lox"), ("reason", &"no such file")],
+    );
+    assert_eq!(msg, "no such file, matey: 'x.lox'");
+    assert_eq!(
+        LexErrorKind::UnexpectedCharacter('@').to_string(),
+        "Unexpected character '@'."
+    );
+}
@@ -0,0 +1,92 @@
+// every operator the scanner knows, scanned alone and glued to its
+// neighbours, with the token and lexeme each one produces
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+use loxrs::data::types::TokenType;
+
+fn scan(source: &str, names: &mut Interner) -> Vec<Token> {
+    let (tokens, errors) = Scanner::new(FileId::default(), source.to_string(), names).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    tokens
+}
+
+// the kind and lexeme of every token before EOF
+fn lexed(source: &str) -> Vec<(TokenType, String)> {
+    let mut names = Interner::new();
+    let tokens = scan(source, &mut names);
+    let (end, tokens) = tokens.split_last().unwrap();
+    assert_eq!(*end.tt(), TokenType::End);
+    tokens
+        .iter()
+        .map(|t| (t.tt().clone(), t.lexeme(&names).into_owned()))
+        .collect()
+}
+
+const OPERATORS: [(&str, TokenType); 28] = [
+    ("(", TokenType::LeftParen),
+    (")", TokenType::RightParen),
+    ("{", TokenType::LeftBrace),
+    ("}", TokenType::RightBrace),
+    (",", TokenType::Comma),
+    (".", TokenType::Dot),
+    ("-", TokenType::Minus),
+    ("+", TokenType::Plus),
+    (";", TokenType::Semicolon),
+    ("/", TokenType::Slash),
+    ("*", TokenType::Star),
+    ("?", TokenType::Question),
+    (":", TokenType::Colon),
+    ("%", TokenType::Percent),
+    ("&", TokenType::Ampersand),
+    ("|", TokenType::Pipe),
+    ("^", TokenType::Caret),
+    ("~", TokenType::Tilde),
+    ("!", TokenType::Bang),
+    ("!=", TokenType::BangEqual),
+    ("=", TokenType::Equal),
+    ("==", TokenType::EqualEqual),
+    (">", TokenType::Greater),
+    (">=", TokenType::GreaterEqual),
+    (">>", TokenType::GreaterGreater),
+    ("<", TokenType::Less),
+    ("<=", TokenType::LessEqual),
+    ("<<", TokenType::LessLess),
+];
+
+#[test]
+fn each_operator_alone() {
+    for (source, tt) in OPERATORS {
+        assert_eq!(lexed(source), [(tt, source.to_string())], "{:?}", source);
+        let tokens = scan(source, &mut Interner::new());
+        assert_eq!(tokens[0].span().len(), source.len(), "{:?}", source);
+    }
+}
+
+#[test]
+fn longest_match_wins() {
+    let kinds =
+        |source: &str| -> Vec<TokenType> { lexed(source).into_iter().map(|(tt, _)| tt).collect() };
+    assert_eq!(kinds("!==="), [TokenType::BangEqual, TokenType::EqualEqual]);
+    assert_eq!(
+        kinds(">==<=="),
+        [
+            TokenType::GreaterEqual,
+            TokenType::Equal,
+            TokenType::LessEqual,
+            TokenType::Equal
+        ]
+    );
+    assert_eq!(
+        kinds("<<<>>>="),
+        [
+            TokenType::LessLess,
+            TokenType::Less,
+            TokenType::GreaterGreater,
+            TokenType::GreaterEqual
+        ]
+    );
+    assert_eq!(kinds("=!"), [TokenType::Equal, TokenType::Bang]);
+}
@@ -0,0 +1,543 @@
+// the shape of parsed programs, written with AstPrinter to keep the
+// expectations readable, and the tokens parse errors point at
+
+use loxrs::backend::parser::Parser;
+use loxrs::backend::printer::AstPrinter;
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::ast::{Expr, ExprKind, Stmt, StmtKind};
+use loxrs::data::error::{ParseError, ParseErrorKind};
+use loxrs::data::limits::Limits;
+use loxrs::data::options::{Extension, Lang, ParserOptions};
+use loxrs::data::source::FileId;
+use loxrs::data::span::Span;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+use loxrs::data::types::TokenType;
+
+// the tokens of a source that scans cleanly, and the names in them
+fn scan(source: &str) -> (Vec<Token>, Interner) {
+    let mut names = Interner::new();
+    let (tokens, errors) =
+        Scanner::new(FileId::default(), source.to_string(), &mut names).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    (tokens, names)
+}
+
+fn parse_with(source: &str, limits: Limits) -> Result<Expr, ParseError> {
+    Parser::with_limits(scan(source).0, limits).parse_expression()
+}
+
+fn parse(source: &str) -> Result<Expr, ParseError> {
+    parse_with(source, Limits::default())
+}
+
+fn parse_program(source: &str) -> (Vec<Stmt>, Vec<ParseError>, Interner) {
+    let (tokens, names) = scan(source);
+    let (statements, errors) = Parser::new(tokens).parse();
+    (statements, errors, names)
+}
+
+// the program, or the first error in it
+fn program(source: &str) -> Result<Vec<Stmt>, ParseError> {
+    let (statements, errors, _) = parse_program(source);
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(statements),
+    }
+}
+
+fn printed(names: &Interner, statements: &[Stmt]) -> Vec<String> {
+    let mut printer = AstPrinter::new(names);
+    statements.iter().map(|s| printer.print_stmt(s)).collect()
+}
+
+fn program_shape(source: &str) -> Vec<String> {
+    let (statements, errors, names) = parse_program(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    printed(&names, &statements)
+}
+
+fn shape(source: &str) -> String {
+    let (tokens, names) = scan(source);
+    let expr = Parser::new(tokens).parse_expression().unwrap();
+    AstPrinter::new(&names).print_expr(&expr)
+}
+
+// the source a node was parsed from
+fn text(source: &str, span: Span) -> &str {
+    &source[span.start..span.end]
+}
+
+// the error kind and the lexeme of the token it points at
+fn failure(source: &str) -> (ParseErrorKind, String) {
+    let err = parse(source).unwrap_err();
+    (err.kind, text(source, err.token.span()).to_string())
+}
+
+#[test]
+fn precedence_and_associativity() {
+    assert_eq!(shape("1 + 2 * 3"), "(+ 1 (* 2 3))");
+    assert_eq!(shape("1 - 2 - 3"), "(- (- 1 2) 3)");
+    assert_eq!(shape("-1 < 2 == !false"), "(== (< (- 1) 2) (! false))");
+    assert_eq!(shape("(1 + 2) / --3"), "(/ (group (+ 1 2)) (- (- 3)))");
+    assert_eq!(shape("nil != true >= 1"), "(!= nil (>= true 1))");
+    assert_eq!(
+        shape("a | b ^ c & d << 1 + 2 % 3"),
+        "(| a (^ b (& c (<< d (+ 1 (% 2 3))))))"
+    );
+    assert_eq!(shape("a & 1 == 0"), "(== (& a 1) 0)");
+    assert_eq!(shape("~-a >> 2 >> 1"), "(>> (>> (~ (- a)) 2) 1)");
+}
+
+#[test]
+fn comma_and_conditional() {
+    assert_eq!(shape("a ? b : c ? d : e"), "(?: a b (?: c d e))");
+    assert_eq!(shape("a ? b ? c : d : e"), "(?: a (?: b c d) e)");
+    assert_eq!(shape("a or b ? c = 1 : d"), "(?: (or a b) (= c 1) d)");
+    assert_eq!(shape("a = 1, b = 2, c"), "(, (, (= a 1) (= b 2)) c)");
+    assert_eq!(shape("a ? b, c : d, e"), "(, (?: a (, b c) d) e)");
+    // a comma in an argument list separates arguments, unless in parens
+    assert_eq!(shape("f(a, (b, c))"), "(call f a (group (, b c)))");
+    assert_eq!(
+        failure("a ? b : c = d"),
+        (ParseErrorKind::InvalidAssignmentTarget, "=".to_string())
+    );
+}
+
+#[test]
+fn strings() {
+    assert_eq!(shape("\"a\" + `b\\n`"), r#"(+ "a" "b\\n")"#);
+    assert_eq!(
+        shape("\"x ${1 + 2} y${\"z\"}\""),
+        r#"(str "x " (+ 1 2) " y" "z")"#
+    );
+}
+
+#[test]
+fn errors_point_at_the_offending_token() {
+    assert_eq!(
+        failure("1 +"),
+        (ParseErrorKind::ExpectExpression, String::new())
+    );
+    assert_eq!(
+        failure("(1 2"),
+        (ParseErrorKind::ExpectRightParen, "2".to_string())
+    );
+    assert_eq!(failure("1 )"), (ParseErrorKind::ExpectEnd, ")".to_string()));
+    assert_eq!(
+        failure("\"${1 2}\""),
+        (ParseErrorKind::ExpectInterpEnd, "2".to_string())
+    );
+    // a '?' without a ':' is pinned on the '?'
+    assert_eq!(
+        failure("a ? b ? c : d"),
+        (ParseErrorKind::ConditionalMissingColon, "?".to_string())
+    );
+    let err = parse("a ? b ? c : d").unwrap_err();
+    assert_eq!(err.span().column, 3);
+}
+
+#[test]
+fn nesting_is_capped() {
+    let limits = Limits {
+        max_nesting_depth: 8,
+        ..Limits::default()
+    };
+    assert!(parse_with(&"-".repeat(6), limits)
+        .is_err_and(|e| e.kind == ParseErrorKind::ExpectExpression));
+    let source = format!("{}1", "-".repeat(100));
+    let err = parse_with(&source, limits).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::NestingTooDeep(8));
+    assert_eq!(text(&source, err.token.span()), "-");
+
+    // far past the default, and still an error rather than a stack overflow
+    assert!(parse(&format!("{}1", "!".repeat(100_000))).is_err());
+}
+
+#[test]
+fn flat_chains_dont_nest() {
+    // a run of operators or calls is a loop, not recursion, so it's good
+    // for far more than the nesting limit
+    let sum = |n: usize| vec!["1"; n].join(" + ");
+    let flat = [
+        sum(1_000),
+        vec!["a"; 1_000].join(" or "),
+        format!("a{}", ".b".repeat(1_000)),
+        format!("f{}", "()".repeat(1_000)),
+        format!("f{}", "(1 + 1, -x)".repeat(500)),
+    ];
+    for source in flat {
+        assert!(parse(&source).is_ok(), "{}", &source[..20]);
+    }
+    // grouping still nests, with or without a chain inside
+    let limits = Limits {
+        max_nesting_depth: 8,
+        ..Limits::default()
+    };
+    let grouped = format!("{}1 + 1{}", "(".repeat(10), ")".repeat(10));
+    assert_eq!(
+        parse_with(&grouped, limits).unwrap_err().kind,
+        ParseErrorKind::NestingTooDeep(8)
+    );
+}
+
+#[test]
+fn long_chains_are_capped() {
+    // the tree is still as deep as the chain is long, so there's a cap of
+    // its own, well past the nesting limit
+    let sum = |n: usize| vec!["1"; n].join(" + ");
+    assert!(parse(&sum(4_096)).is_ok());
+    let chains = [
+        sum(20_000),
+        vec!["a"; 20_000].join(" or "),
+        format!("a{}", ".b".repeat(20_000)),
+        format!("f{}", "()".repeat(20_000)),
+    ];
+    for source in chains {
+        let err = parse(&source).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ChainTooLong(4096));
+    }
+    let limits = Limits {
+        max_chain_length: 3,
+        ..Limits::default()
+    };
+    assert!(parse_with("1 + 2 + 3 + 4", limits).is_ok());
+    let source = "1 + 2 + 3 + 4 + 5";
+    let err = parse_with(source, limits).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::ChainTooLong(3));
+    assert_eq!(err.span().column, 15);
+
+    // each chain counts from zero, nested ones included
+    assert!(parse_with("(1 + 2 + 3) + (4 + 5 + 6) + f(1 + 2)", limits).is_ok());
+
+    // and the statements after one parse as usual
+    let (statements, errors, names) =
+        parse_program(&format!("print {};\nprint 1 + 2;", sum(20_000)));
+    let errors: Vec<_> = errors.into_iter().map(|e| e.kind).collect();
+    assert_eq!(errors, [ParseErrorKind::ChainTooLong(4096)]);
+    assert_eq!(printed(&names, &statements), ["(print (+ 1 2))"]);
+}
+
+#[test]
+fn spans() {
+    // every node spans the source it came from, operators included
+    let source = "-a.b * (c = 2) + \"x${y}\"";
+    let expr = parse(source).unwrap();
+    assert_eq!(text(source, expr.span), source);
+    let ExprKind::Binary { left, op, right } = &expr.node else {
+        panic!("{:?}", expr);
+    };
+    assert_eq!((text(source, op.span()), op.span().column), ("+", 16));
+    assert_eq!(text(source, left.span), "-a.b * (c = 2)");
+    assert_eq!(text(source, right.span), "\"x${y}\"");
+    let ExprKind::Binary { left, right, .. } = &left.node else {
+        panic!("{:?}", left);
+    };
+    assert_eq!(text(source, left.span), "-a.b");
+    assert_eq!(text(source, right.span), "(c = 2)");
+    let ExprKind::Unary { right, .. } = &left.node else {
+        panic!("{:?}", left);
+    };
+    assert_eq!(text(source, right.span), "a.b");
+
+    // statements run through their semicolon or closing brace
+    let source = "var a = 1;\nif (a) { print a; } else return;\nclass C < B {}";
+    let statements = program(source).unwrap();
+    let spans: Vec<&str> = statements.iter().map(|s| text(source, s.span)).collect();
+    assert_eq!(
+        spans,
+        [
+            "var a = 1;",
+            "if (a) { print a; } else return;",
+            "class C < B {}"
+        ]
+    );
+
+    // a desugared for loop spans the whole loop, and its missing condition
+    // is pinned on the keyword
+    let source = "for (;;) x;";
+    let statements = program(source).unwrap();
+    assert_eq!(text(source, statements[0].span), source);
+    let StmtKind::While { condition, .. } = &statements[0].node else {
+        panic!("{:?}", statements[0]);
+    };
+    assert_eq!(text(source, condition.span), "for");
+}
+
+#[test]
+fn classic_lox_reports_extensions() {
+    let source = "print `a` + \"${b}\", c ? d : e;\nprint \"plain\" + f;";
+    let options = ParserOptions::new().lang(Lang::Classic);
+    let (statements, errors) = Parser::with_options(scan(source).0, options).parse();
+    let errors: Vec<(ParseErrorKind, String)> = errors
+        .into_iter()
+        .map(|e| (e.kind, text(source, e.token.span()).to_string()))
+        .collect();
+    let not_enabled = |extension, lexeme: &str| {
+        (
+            ParseErrorKind::ExtensionNotEnabled(extension),
+            lexeme.to_string(),
+        )
+    };
+    assert_eq!(
+        errors,
+        [
+            not_enabled(Extension::RawStrings, "`a`"),
+            not_enabled(Extension::Interpolation, "${"),
+            not_enabled(Extension::Comma, ","),
+            not_enabled(Extension::Conditional, "?"),
+        ]
+    );
+    let bitwise = "~a % b << c";
+    let err = Parser::with_options(scan(bitwise).0, options)
+        .parse_expression()
+        .unwrap_err();
+    assert_eq!(
+        (err.kind, text(bitwise, err.token.span()).to_string()),
+        not_enabled(Extension::Bitwise, "~")
+    );
+    // the parse carries on regardless
+    assert_eq!(statements.len(), 2);
+
+    assert!(program(source).is_ok());
+}
+
+#[test]
+fn statements() {
+    assert_eq!(
+        program_shape("var a = 1; var b; print a + b;\na = b = 2;"),
+        [
+            "(var a 1)",
+            "(var b)",
+            "(print (+ a b))",
+            "(; (= a (= b 2)))"
+        ]
+    );
+    assert!(program("").unwrap().is_empty());
+}
+
+#[test]
+fn statement_errors() {
+    let failure = |source: &str| {
+        let err = program(source).unwrap_err();
+        (err.kind, text(source, err.token.span()).to_string())
+    };
+    assert_eq!(
+        failure("var 1;"),
+        (ParseErrorKind::ExpectVariableName, "1".to_string())
+    );
+    assert_eq!(
+        failure("print 1 print 2;"),
+        (
+            ParseErrorKind::ExpectSemicolonAfterValue,
+            "print".to_string()
+        )
+    );
+    assert_eq!(
+        failure("var a = 1"),
+        (ParseErrorKind::ExpectSemicolonAfterVar, String::new())
+    );
+    assert_eq!(
+        failure("a + b = c;"),
+        (ParseErrorKind::InvalidAssignmentTarget, "=".to_string())
+    );
+}
+
+#[test]
+fn blocks() {
+    assert_eq!(
+        program_shape("{ var a = 1; { print a; } {} }"),
+        ["(block (var a 1) (block (print a)) (block))"]
+    );
+
+    // the error points back at the brace left open, not at the end of input
+    let source = "{\n  {\n    print 1;\n  }\n\nprint 2;\n";
+    let err = program(source).unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::UnclosedBlock);
+    assert_eq!((err.span().line, err.span().column), (1, 1));
+    assert_eq!(text(source, err.token.span()), "{");
+}
+
+#[test]
+fn control_flow() {
+    // the else belongs to the inner if
+    assert_eq!(
+        program_shape("if (a) if (b) print 1; else print 2;"),
+        ["(if a (if b (print 1) (print 2)))"]
+    );
+    assert_eq!(
+        program_shape("while (a or b and c) a = a - 1;"),
+        ["(while (or a (and b c)) (; (= a (- a 1))))"]
+    );
+    assert_eq!(
+        program_shape("for (var i = 0; i < 3; i = i + 1) print i;"),
+        ["(block (var i 0) (while (< i 3) (block (print i) (; (= i (+ i 1))))))"]
+    );
+    assert_eq!(program_shape("for (;;) {}"), ["(while true (block))"]);
+    assert_eq!(
+        program_shape("for (i = 0; ; ) print i;"),
+        ["(block (; (= i 0)) (while true (print i)))"]
+    );
+}
+
+#[test]
+fn control_flow_errors() {
+    let failure = |source: &str| program(source).unwrap_err().kind;
+    assert_eq!(
+        failure("while x) {}"),
+        ParseErrorKind::ExpectLeftParenAfter(TokenType::While)
+    );
+    assert_eq!(
+        failure("if (x {}"),
+        ParseErrorKind::ExpectRightParenAfterCondition
+    );
+    assert_eq!(
+        failure("for (;x) {}"),
+        ParseErrorKind::ExpectSemicolonAfterLoopCondition
+    );
+    assert_eq!(
+        failure("for (;; x {}"),
+        ParseErrorKind::ExpectRightParenAfterForClauses
+    );
+}
+
+#[test]
+fn functions_and_calls() {
+    assert_eq!(
+        program_shape("fun add(a, b) { return a + b; } fun f() { return; }"),
+        ["(fun add (a b) (return (+ a b)))", "(fun f () (return))"]
+    );
+    assert_eq!(
+        program_shape("-f(1)(g(), 2)();"),
+        ["(; (- (call (call (call f 1) (call g) 2))))"]
+    );
+
+    // spans cover the whole declaration and the whole call, for runtime errors
+    let source = "fun f(x) { x; }\nprint f(1 + 2)(3);";
+    let statements = program(source).unwrap();
+    assert_eq!(text(source, statements[0].span), "fun f(x) { x; }");
+    let StmtKind::Print(call) = &statements[1].node else {
+        panic!("{:?}", statements[1]);
+    };
+    assert_eq!(text(source, call.span), "f(1 + 2)(3)");
+    assert_eq!((call.span.line, call.span.column), (2, 7));
+}
+
+#[test]
+fn function_errors() {
+    let failure = |source: &str| {
+        let err = program(source).unwrap_err();
+        (err.kind, text(source, err.token.span()).to_string())
+    };
+    assert_eq!(
+        failure("f(1, 2,);"),
+        (ParseErrorKind::TrailingComma, ",".to_string())
+    );
+    assert_eq!(
+        failure("fun f(a, b,) {}"),
+        (ParseErrorKind::TrailingComma, ",".to_string())
+    );
+    assert_eq!(
+        failure("fun f(a, 1) {}"),
+        (ParseErrorKind::ExpectParameterName, "1".to_string())
+    );
+    assert_eq!(
+        failure("fun f() return;"),
+        (
+            ParseErrorKind::ExpectLeftBraceBeforeBody,
+            "return".to_string()
+        )
+    );
+
+    let args = |n: usize| vec!["x"; n].join(", ");
+    assert!(program(&format!("f({});", args(255))).is_ok());
+    assert_eq!(
+        failure(&format!("f({});", args(256))).0,
+        ParseErrorKind::TooManyArguments(255)
+    );
+    assert_eq!(
+        failure(&format!("fun f({}) {{}}", args(256))).0,
+        ParseErrorKind::TooManyParameters(255)
+    );
+}
+
+#[test]
+fn classes() {
+    assert_eq!(
+        program_shape(
+            "class A < B { init(x) { this.x = x; } get() { return super.get(); } } class C {}"
+        ),
+        [
+            "(class A < B (method init (x) (; (= (. this x) x))) (method get () (return (call (super get)))))",
+            "(class C)"
+        ]
+    );
+    assert_eq!(
+        program_shape("a.b(1).c = d.e;"),
+        ["(; (= (. (call (. a b) 1) c) (. d e)))"]
+    );
+
+    // a method's span starts at its name
+    let source = "class A {\n  m() {}\n}";
+    let statements = program(source).unwrap();
+    let StmtKind::Class { methods, .. } = &statements[0].node else {
+        panic!("{:?}", statements[0]);
+    };
+    assert_eq!(text(source, methods[0].span), "m() {}");
+}
+
+#[test]
+fn class_errors() {
+    let failure = |source: &str| program(source).unwrap_err().kind;
+    assert_eq!(failure("class {}"), ParseErrorKind::ExpectClassName);
+    assert_eq!(
+        failure("class A < {}"),
+        ParseErrorKind::ExpectSuperclassName
+    );
+    assert_eq!(
+        failure("class A m() {}"),
+        ParseErrorKind::ExpectLeftBraceBeforeClassBody
+    );
+    assert_eq!(
+        failure("class A { fun m() {} }"),
+        ParseErrorKind::ExpectMethodName
+    );
+    assert_eq!(failure("class A { m() {}"), ParseErrorKind::UnclosedBlock);
+    assert_eq!(failure("super;"), ParseErrorKind::ExpectDotAfterSuper);
+    assert_eq!(
+        failure("super.1;"),
+        ParseErrorKind::ExpectSuperclassMethodName
+    );
+    assert_eq!(failure("a.;"), ParseErrorKind::ExpectPropertyName);
+    assert_eq!(
+        failure("a.b() = 1;"),
+        ParseErrorKind::InvalidAssignmentTarget
+    );
+}
+
+#[test]
+fn recovers_at_statement_boundaries() {
+    let source = "var a = ;\nprint 1 print 2;\nfun f(a,) { return a + ; }\n1 = 2;\nclass C { m( }\nvar ok = 1;";
+    let (statements, errors, names) = parse_program(source);
+    let errors: Vec<(ParseErrorKind, u32)> = errors
+        .into_iter()
+        .map(|e| (e.kind, e.token.line()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (ParseErrorKind::ExpectExpression, 1),
+            (ParseErrorKind::ExpectSemicolonAfterValue, 2),
+            (ParseErrorKind::TrailingComma, 3),
+            (ParseErrorKind::ExpectExpression, 3),
+            (ParseErrorKind::InvalidAssignmentTarget, 4),
+            (ParseErrorKind::ExpectParameterName, 5),
+        ]
+    );
+
+    // statements with errors that didn't stop the parse are kept
+    assert_eq!(
+        printed(&names, &statements),
+        ["(fun f (a))", "(; 1)", "(var ok 1)"]
+    );
+}
@@ -0,0 +1,466 @@
+// property tests over randomly generated programs: whatever the parser
+// builds from one can be written back out as lox that parses to the same
+// tree. trees are compared by their AstPrinter dumps, since the spans in
+// them move when the source is reformatted
+
+use proptest::prelude::*;
+
+use loxrs::backend::parser::Parser;
+use loxrs::backend::printer::AstPrinter;
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::ast::{Expr, ExprKind, ExprVisitor, Function, Literal, Stmt, StmtVisitor};
+use loxrs::data::source::FileId;
+use loxrs::data::span::Span;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+
+fn parse(source: &str) -> (Vec<Stmt>, Interner) {
+    let mut names = Interner::new();
+    let (tokens, errors) =
+        Scanner::new(FileId::default(), source.to_string(), &mut names).scan_tokens();
+    assert_eq!(errors, [], "{}", source);
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{:?} in {}", errors, source);
+    (statements, names)
+}
+
+fn dump((statements, names): &(Vec<Stmt>, Interner)) -> String {
+    let mut printer = AstPrinter::new(names);
+    let printed: Vec<_> = statements.iter().map(|s| printer.print_stmt(s)).collect();
+    printed.join("\n")
+}
+
+fn format((statements, names): &(Vec<Stmt>, Interner)) -> String {
+    let mut formatter = Formatter { names };
+    let formatted: Vec<_> = statements
+        .iter()
+        .map(|s| s.accept(&mut formatter))
+        .collect();
+    formatted.join("\n")
+}
+
+// writes a tree back out as lox. it only adds the parentheses that were
+// grouping nodes in the source, so it relies on the tree having come from
+// the parser: every operand already binds the way it did there. for loops
+// come back as the while loops they were desugared into
+struct Formatter<'a> {
+    names: &'a Interner,
+}
+
+impl Formatter<'_> {
+    fn expr(&mut self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    fn block(&mut self, statements: &[Stmt]) -> String {
+        let statements: Vec<_> = statements.iter().map(|s| s.accept(self)).collect();
+        format!("{{ {} }}", statements.join(" "))
+    }
+
+    fn function(&mut self, function: &Function) -> String {
+        let params: Vec<_> = function
+            .params
+            .iter()
+            .map(|p| p.lexeme(self.names))
+            .collect();
+        let body = self.block(&function.body);
+        format!(
+            "{}({}) {}",
+            function.name.lexeme(self.names),
+            params.join(", "),
+            body
+        )
+    }
+}
+
+// the escapes a string part needs to scan back to the same text
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '$' => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl StmtVisitor<String> for Formatter<'_> {
+    fn visit_expression(&mut self, _: Span, expr: &Expr) -> String {
+        format!("{};", self.expr(expr))
+    }
+
+    fn visit_print(&mut self, _: Span, expr: &Expr) -> String {
+        format!("print {};", self.expr(expr))
+    }
+
+    fn visit_var(&mut self, _: Span, name: &Token, initializer: Option<&Expr>) -> String {
+        match initializer {
+            Some(init) => format!("var {} = {};", name.lexeme(self.names), self.expr(init)),
+            None => format!("var {};", name.lexeme(self.names)),
+        }
+    }
+
+    fn visit_block(&mut self, _: Span, statements: &[Stmt]) -> String {
+        self.block(statements)
+    }
+
+    fn visit_class(
+        &mut self,
+        _: Span,
+        name: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Function],
+    ) -> String {
+        let mut out = format!("class {}", name.lexeme(self.names));
+        if let Some(superclass) = superclass {
+            out += &format!(" < {}", self.expr(superclass));
+        }
+        let methods: Vec<_> = methods.iter().map(|m| self.function(m)).collect();
+        format!("{} {{ {} }}", out, methods.join(" "))
+    }
+
+    fn visit_function(&mut self, _: Span, function: &Function) -> String {
+        format!("fun {}", self.function(function))
+    }
+
+    fn visit_if(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> String {
+        let mut out = format!("if ({}) {}", self.expr(condition), then_branch.accept(self));
+        if let Some(else_branch) = else_branch {
+            out += &format!(" else {}", else_branch.accept(self));
+        }
+        out
+    }
+
+    fn visit_return(&mut self, _: Span, _: &Token, value: Option<&Expr>) -> String {
+        match value {
+            Some(value) => format!("return {};", self.expr(value)),
+            None => "return;".to_string(),
+        }
+    }
+
+    fn visit_while(&mut self, _: Span, condition: &Expr, body: &Stmt) -> String {
+        format!("while ({}) {}", self.expr(condition), body.accept(self))
+    }
+}
+
+impl ExprVisitor<String> for Formatter<'_> {
+    fn visit_binary(&mut self, _: Span, left: &Expr, op: &Token, right: &Expr) -> String {
+        format!(
+            "{} {} {}",
+            self.expr(left),
+            op.lexeme(self.names),
+            self.expr(right)
+        )
+    }
+
+    fn visit_unary(&mut self, _: Span, op: &Token, right: &Expr) -> String {
+        format!("{}{}", op.lexeme(self.names), self.expr(right))
+    }
+
+    fn visit_logical(&mut self, span: Span, left: &Expr, op: &Token, right: &Expr) -> String {
+        self.visit_binary(span, left, op, right)
+    }
+
+    fn visit_conditional(
+        &mut self,
+        _: Span,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+    ) -> String {
+        let (condition, then_branch) = (self.expr(condition), self.expr(then_branch));
+        format!(
+            "{} ? {} : {}",
+            condition,
+            then_branch,
+            self.expr(else_branch)
+        )
+    }
+
+    fn visit_call(&mut self, _: Span, callee: &Expr, _: &Token, arguments: &[Expr]) -> String {
+        let arguments: Vec<_> = arguments.iter().map(|a| self.expr(a)).collect();
+        format!("{}({})", self.expr(callee), arguments.join(", "))
+    }
+
+    fn visit_get(&mut self, _: Span, object: &Expr, name: &Token) -> String {
+        format!("{}.{}", self.expr(object), name.lexeme(self.names))
+    }
+
+    fn visit_set(&mut self, span: Span, object: &Expr, name: &Token, value: &Expr) -> String {
+        let target = self.visit_get(span, object, name);
+        format!("{} = {}", target, self.expr(value))
+    }
+
+    fn visit_this(&mut self, _: Span, _: &Token) -> String {
+        "this".to_string()
+    }
+
+    fn visit_super(&mut self, _: Span, _: &Token, method: &Token) -> String {
+        format!("super.{}", method.lexeme(self.names))
+    }
+
+    fn visit_grouping(&mut self, _: Span, inner: &Expr) -> String {
+        format!("({})", self.expr(inner))
+    }
+
+    fn visit_literal(&mut self, _: Span, literal: &Literal) -> String {
+        match literal {
+            Literal::Number(n) => n.to_string(),
+            Literal::String(s) => format!("\"{}\"", escape(s)),
+            Literal::Bool(b) => b.to_string(),
+            Literal::Nil => "nil".to_string(),
+        }
+    }
+
+    fn visit_variable(&mut self, _: Span, name: &Token) -> String {
+        name.lexeme(self.names).into_owned()
+    }
+
+    fn visit_assign(&mut self, _: Span, name: &Token, value: &Expr) -> String {
+        format!("{} = {}", name.lexeme(self.names), self.expr(value))
+    }
+
+    // a string literal among the parts is a piece of the template. the
+    // generator never embeds a bare one, as ${"a"}, which would look the same
+    fn visit_interpolation(&mut self, _: Span, parts: &[Expr]) -> String {
+        let mut out = String::from("\"");
+        for part in parts {
+            match &part.node {
+                ExprKind::Literal(Literal::String(s)) => out += &escape(s),
+                _ => out += &format!("${{{}}}", self.expr(part)),
+            }
+        }
+        out + "\""
+    }
+}
+
+// how loosely a generated expression binds, so it can be parenthesized
+// where it wouldn't parse bare: an assignment can't be an operand, and a
+// comma expression can't be an argument
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Tight, // a primary, call, or property, which can be assigned through
+    Loose, // an operator expression
+    Assign,
+    Comma,
+}
+
+type Generated = (String, Level);
+
+fn wrap((source, level): Generated, max: Level) -> String {
+    if level > max {
+        format!("({})", source)
+    } else {
+        source
+    }
+}
+
+const KEYWORDS: [&str; 16] = [
+    "and", "class", "else", "false", "for", "fun", "if", "nil", "or", "print", "return", "super",
+    "this", "true", "var", "while",
+];
+
+fn name() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,4}".prop_filter("a keyword", |s| !KEYWORDS.contains(&s.as_str()))
+}
+
+fn piece() -> impl Strategy<Value = String> {
+    r#"([a-z ]|\\["\\nt$]){0,6}"#
+}
+
+fn leaf() -> impl Strategy<Value = Generated> {
+    prop_oneof![
+        "[0-9]{1,3}(\\.[0-9]{1,2})?",
+        "0x[0-9a-fA-F]{1,3}",
+        "[1-9]_[0-9]{3}",
+        name(),
+        piece().prop_map(|s| format!("\"{}\"", s)),
+        prop::sample::select(vec!["true", "false", "nil", "this"]).prop_map(str::to_string),
+        name().prop_map(|m| format!("super.{}", m)),
+    ]
+    .prop_map(|s| (s, Level::Tight))
+}
+
+fn expr() -> impl Strategy<Value = Generated> {
+    leaf().prop_recursive(5, 48, 4, |inner| {
+        // calls and properties only stay assignable on something tight
+        let postfix = |object: Generated, suffix: String| {
+            let level = object.1.min(Level::Loose);
+            (wrap(object, Level::Loose) + &suffix, level)
+        };
+        prop_oneof![
+            inner
+                .clone()
+                .prop_map(|e| (format!("({})", e.0), Level::Tight)),
+            (prop::sample::select(vec!["-", "!", "~"]), inner.clone())
+                .prop_map(|(op, e)| (format!("{}{}", op, wrap(e, Level::Loose)), Level::Loose)),
+            (
+                inner.clone(),
+                prop::sample::select(vec![
+                    "+", "-", "*", "/", "%", "==", "!=", "<", "<=", ">", ">=", "&", "|", "^", "<<",
+                    ">>", "and", "or",
+                ]),
+                inner.clone(),
+            )
+                .prop_map(|(l, op, r)| {
+                    let (l, r) = (wrap(l, Level::Loose), wrap(r, Level::Loose));
+                    (format!("{} {} {}", l, op, r), Level::Loose)
+                }),
+            (inner.clone(), inner.clone(), inner.clone()).prop_map(|(c, t, e)| {
+                let (c, t, e) = (wrap(c, Level::Loose), t.0, wrap(e, Level::Loose));
+                (format!("{} ? {} : {}", c, t, e), Level::Loose)
+            }),
+            (inner.clone(), prop::collection::vec(inner.clone(), 0..3)).prop_map(
+                move |(callee, args)| {
+                    let args: Vec<_> = args.into_iter().map(|a| wrap(a, Level::Assign)).collect();
+                    postfix(callee, format!("({})", args.join(", ")))
+                }
+            ),
+            (inner.clone(), name())
+                .prop_map(move |(object, name)| postfix(object, format!(".{}", name))),
+            (name(), inner.clone()).prop_map(|(name, value)| {
+                (
+                    format!("{} = {}", name, wrap(value, Level::Assign)),
+                    Level::Assign,
+                )
+            }),
+            (inner.clone(), name(), inner.clone()).prop_map(|(object, name, value)| {
+                let (object, value) = (wrap(object, Level::Tight), wrap(value, Level::Assign));
+                (format!("{}.{} = {}", object, name, value), Level::Assign)
+            }),
+            (inner.clone(), inner.clone()).prop_map(|(l, r)| {
+                let (l, r) = (l.0, wrap(r, Level::Assign));
+                (format!("{}, {}", l, r), Level::Comma)
+            }),
+            // the embedded expression is always grouped, see visit_interpolation
+            (piece(), inner, piece()).prop_map(|(before, e, after)| {
+                (
+                    format!("\"{}${{({})}}{}\"", before, e.0, after),
+                    Level::Tight,
+                )
+            }),
+        ]
+    })
+}
+
+fn source(e: Generated) -> String {
+    e.0
+}
+
+// a statement, and whether it's a declaration, which can't be the body of
+// an if or a loop without braces around it
+type GeneratedStmt = (String, bool);
+
+fn body((source, declaration): GeneratedStmt) -> String {
+    if declaration {
+        format!("{{ {} }}", source)
+    } else {
+        source
+    }
+}
+
+fn params() -> impl Strategy<Value = String> {
+    prop::collection::vec(name(), 0..3).prop_map(|names| names.join(", "))
+}
+
+fn stmt() -> impl Strategy<Value = GeneratedStmt> {
+    let simple = prop_oneof![
+        expr().prop_map(|e| (format!("{};", source(e)), false)),
+        expr().prop_map(|e| (format!("print {};", source(e)), false)),
+        name().prop_map(|n| (format!("var {};", n), true)),
+        (name(), expr()).prop_map(|(n, e)| (format!("var {} = {};", n, source(e)), true)),
+        Just(("return;".to_string(), false)),
+        expr().prop_map(|e| (format!("return {};", source(e)), false)),
+    ];
+    simple.prop_recursive(3, 24, 4, |inner| {
+        let block = prop::collection::vec(inner.clone(), 0..4)
+            .prop_map(|body| body.into_iter().map(|s| s.0).collect::<Vec<_>>().join(" "));
+        prop_oneof![
+            block.clone().prop_map(|b| (format!("{{ {} }}", b), false)),
+            (expr(), inner.clone(), prop::option::of(inner.clone())).prop_map(|(c, t, e)| {
+                let mut out = format!("if ({}) {}", source(c), body(t));
+                if let Some(e) = e {
+                    out += &format!(" else {}", body(e));
+                }
+                (out, false)
+            }),
+            (expr(), inner.clone())
+                .prop_map(|(c, b)| (format!("while ({}) {}", source(c), body(b)), false)),
+            (
+                prop_oneof![
+                    Just(";".to_string()),
+                    (name(), expr()).prop_map(|(n, e)| format!("var {} = {};", n, source(e))),
+                    expr().prop_map(|e| format!("{};", source(e))),
+                ],
+                prop::option::of(expr()),
+                prop::option::of(expr()),
+                inner.clone(),
+            )
+                .prop_map(|(init, cond, incr, b)| {
+                    let (cond, incr) = (
+                        cond.map_or(String::new(), source),
+                        incr.map_or(String::new(), source),
+                    );
+                    (
+                        format!("for ({} {}; {}) {}", init, cond, incr, body(b)),
+                        false,
+                    )
+                }),
+            (name(), params(), block.clone())
+                .prop_map(|(n, p, b)| (format!("fun {}({}) {{ {} }}", n, p, b), true)),
+            (
+                name(),
+                prop::option::of(name()),
+                prop::collection::vec((name(), params(), block), 0..3),
+            )
+                .prop_map(|(n, superclass, methods)| {
+                    let superclass = superclass.map_or(String::new(), |s| format!(" < {}", s));
+                    let methods: Vec<_> = methods
+                        .into_iter()
+                        .map(|(m, p, b)| format!("{}({}) {{ {} }}", m, p, b))
+                        .collect();
+                    (
+                        format!("class {}{} {{ {} }}", n, superclass, methods.join(" ")),
+                        true,
+                    )
+                }),
+        ]
+    })
+}
+
+fn program() -> impl Strategy<Value = String> {
+    prop::collection::vec(stmt(), 0..8).prop_map(|stmts| {
+        stmts
+            .into_iter()
+            .map(|s| s.0)
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+proptest! {
+    #[test]
+    fn formatting_reparses_to_the_same_tree(src in program()) {
+        let tree = parse(&src);
+        let formatted = format(&tree);
+        prop_assert_eq!(dump(&parse(&formatted)), dump(&tree), "formatted as {}", formatted);
+    }
+
+    #[test]
+    fn formatting_and_reparsing_is_idempotent(src in program()) {
+        let once = format(&parse(&src));
+        let twice = format(&parse(&once));
+        prop_assert_eq!(twice, once);
+    }
+}
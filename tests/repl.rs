@@ -0,0 +1,118 @@
+// the repl, fed through a pipe: which lines it runs together as one program,
+// and recording and replaying sessions
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+
+// stdout and stderr of a session typing (or pasting) the given input
+fn session(input: &str) -> (String, String) {
+    session_with(&["--print-ast"], input)
+}
+
+fn session_with(flags: &[&str], input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxrs"))
+        .args(flags)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run loxrs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn unfinished_lines_wait_for_the_rest() {
+    let (stdout, stderr) =
+        session("class A {\n  m() {\n    print \"a\nb\";\n  }\n}\nprint (1 +\n 2);\n");
+    assert_eq!(
+        stdout,
+        "(class A (method m () (print \"a\\nb\")))\n(print (group (+ 1 2)))\n"
+    );
+    assert_eq!(stderr, "");
+
+    // a blank line gives up waiting, and the lines so far are run as they are
+    let (stdout, stderr) = session("print {\n\nprint 1;\n");
+    assert_eq!(stdout, "(print 1)\n");
+    assert_eq!(
+        stderr,
+        "[line 1:7] Error in <repl:1> at '{': Expect expression.\n"
+    );
+}
+
+#[test]
+fn pastes_run_as_one_program() {
+    // the paste ends partway through a line, with more typed after it
+    let (stdout, stderr) = session("\x1b[200~print 1 +\n\n2;\n\x1b[201~print 3;\n");
+    assert_eq!(stdout, "(print (+ 1 2))\n(print 3)\n");
+    assert_eq!(stderr, "");
+
+    // commands are only commands when typed
+    let (_, stderr) = session("\x1b[200~:stop\x1b[201~\n");
+    assert_eq!(
+        stderr,
+        "[line 1:1] Error in <repl:1> at ':': Expect expression.\n"
+    );
+}
+
+#[test]
+fn programs_print_only_what_is_asked_for() {
+    // a clean parse is silent by default
+    assert_eq!(
+        session_with(&[], "print 1;\n"),
+        (String::new(), String::new())
+    );
+
+    // the debug form, internals and all, is only behind its own flag
+    let (stdout, _) = session_with(&["--debug-ast"], "print 1;\n");
+    assert!(stdout.starts_with("Spanned { node: Print("), "{}", stdout);
+    let (stdout, _) = session_with(&["--print-ast", "--debug-ast"], "print 1;\n");
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", stdout);
+    assert_eq!(lines[0], "(print 1)");
+}
+
+// a path under the temp dir, unique to this test run
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("loxrs-{}-{}", process::id(), name))
+}
+
+#[test]
+fn recordings_replay_the_same() {
+    let log = temp_path("session.lox");
+    let input = format!(
+        ":record {log}\nprint 1;\n\x1b[200~print 2 +\n\n3;\x1b[201~\n:stop\nprint 4;\n:replay {log}\n",
+        log = log.display()
+    );
+    let (stdout, stderr) = session(&input);
+    fs::remove_file(&log).unwrap();
+    // the paste, blank line and all, runs as one program both times
+    assert_eq!(
+        stdout,
+        "(print 1)\n(print (+ 2 3))\n(print 4)\n(print 1)\n(print (+ 2 3))\n"
+    );
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn replays_skip_commands() {
+    // a log that replays itself runs once, rather than until the stack runs out
+    let log = temp_path("recursive.lox");
+    fs::write(&log, format!(":replay {}\nprint 1;\n", log.display())).unwrap();
+    let (stdout, stderr) = session(&format!(":replay {}\n", log.display()));
+    fs::remove_file(&log).unwrap();
+    assert_eq!(stdout, "(print 1)\n");
+    assert_eq!(stderr, "");
+}
@@ -0,0 +1,110 @@
+// the switches in ScannerOptions, which the golden tests can't reach since
+// the driver always scans with the defaults
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::error::{LexError, LexErrorKind};
+use loxrs::data::options::ScannerOptions;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+use loxrs::data::types::TokenType;
+
+fn scan(source: &str, options: ScannerOptions) -> (Vec<Token>, Vec<LexError>, Interner) {
+    let mut names = Interner::new();
+    let source = source.to_string();
+    let (tokens, errors) =
+        Scanner::with_options(FileId::default(), source, &mut names, options).scan_tokens();
+    (tokens, errors, names)
+}
+
+fn lexemes(names: &Interner, tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|t| t.lexeme(names).into_owned())
+        .collect()
+}
+
+#[test]
+fn comments_are_kept_as_tokens() {
+    let source = "#!/usr/bin/env loxrs\nvar a; // note\n/* block /* nested */ */ a";
+    let (tokens, errors, names) = scan(source, ScannerOptions::new().keep_comments(true));
+    assert!(errors.is_empty());
+    assert_eq!(
+        lexemes(&names, &tokens),
+        [
+            "#!/usr/bin/env loxrs",
+            "var",
+            "a",
+            ";",
+            "// note",
+            "/* block /* nested */ */",
+            "a",
+            ""
+        ]
+    );
+    assert_eq!(*tokens[4].tt(), TokenType::Comment("// note".to_string()));
+
+    // and dropped by default
+    let (tokens, _, names) = scan(source, ScannerOptions::new());
+    assert_eq!(lexemes(&names, &tokens), ["var", "a", ";", "a", ""]);
+}
+
+#[test]
+fn non_ascii_can_be_rejected() {
+    let source = "var café = \"ok\";\nprint \"crème\nbrûlée\"; // ☕";
+    let (_, errors, _) = scan(source, ScannerOptions::new());
+    assert!(errors.is_empty());
+
+    let (tokens, errors, _) = scan(source, ScannerOptions::new().allow_non_ascii(false));
+    let found: Vec<_> = errors
+        .iter()
+        .map(|err| (err.kind.clone(), err.span.line, err.span.column))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (LexErrorKind::NonAscii('é'), 1, 8),
+            (LexErrorKind::NonAscii('è'), 2, 10),
+            (LexErrorKind::NonAscii('☕'), 3, 13),
+        ]
+    );
+    // the tokens are still there, for tools that want to carry on
+    assert_eq!(tokens.len(), 9);
+}
+
+#[test]
+fn token_count_is_capped() {
+    let options = ScannerOptions::new().max_tokens(3);
+    let (tokens, errors, _) = scan("a b c   // trailing trivia is fine\n", options);
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), 4);
+
+    let (tokens, errors, names) = scan("a b c d e f", options);
+    assert_eq!(lexemes(&names, &tokens), ["a", "b", "c", ""]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexErrorKind::TooManyTokens(3));
+    assert_eq!(errors[0].span.start, 6);
+
+    // a lexeme that queues two tokens is cut off in the middle
+    let (tokens, errors, names) = scan("a b \"x ${y} z\"", options);
+    assert_eq!(lexemes(&names, &tokens), ["a", "b", "x ", ""]);
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn builder_sets_limits() {
+    let options = ScannerOptions::new()
+        .max_string_len(3)
+        .max_number_len(2)
+        .max_nesting_depth(1);
+    let (_, errors, _) = scan("\"abcd\" 123 ((", options);
+    let kinds: Vec<_> = errors.into_iter().map(|err| err.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            LexErrorKind::StringTooLong { len: 4, max: 3 },
+            LexErrorKind::NumberTooLong { len: 3, max: 2 },
+            LexErrorKind::NestingTooDeep(1),
+        ]
+    );
+}
@@ -0,0 +1,171 @@
+// property tests over randomly generated token soup, which needn't parse.
+// the round trips through the parser are in parser_props.rs
+
+use proptest::prelude::*;
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::edit::TextEdit;
+use loxrs::data::source::FileId;
+use loxrs::data::symbol::Interner;
+use loxrs::data::token::Token;
+use loxrs::data::types::TokenType;
+
+// tokens compared across scans need the same interner, or the same name
+// could come back as two different symbols
+fn scan(source: &str, names: &mut Interner) -> Vec<Token> {
+    let (tokens, errors) = Scanner::new(FileId::default(), source.to_string(), names).scan_tokens();
+    assert_eq!(errors, [], "generated source should scan");
+    tokens
+}
+
+fn lexeme() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(vec![
+            "(", ")", "{", "}", ",", ".", "-", "+", ";", "/", "*", "?", ":", "%", "&", "|", "^",
+            "~", "!", "!=", "=", "==", ">", ">=", ">>", "<", "<=", "<<",
+        ])
+        .prop_map(str::to_string),
+        r#"([a-z \néü😀]|\\["\\ntr]){0,12}"#.prop_map(|s| format!("\"{}\"", s)),
+        r#"[a-z \n"\\${}é]{0,12}"#.prop_map(|s| format!("`{}`", s)),
+        "[0-9](_?[0-9]){0,5}(\\.[0-9]{1,4})?([eE][+-]?[0-9]{1,2})?",
+        "0[xX][0-9a-fA-F](_?[0-9a-fA-F]){0,7}",
+        "0[bB][01](_?[01]){0,11}",
+        "[a-zA-Z_][a-zA-Z0-9_]{0,8}", // identifiers, and keywords by chance
+        "[a-zé_π][a-z0-9éπ]{0,4}",    // non-ascii identifiers
+    ]
+}
+
+fn trivia() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[ \t\r\n]{1,4}",
+        "[a-z éü😀]{0,12}".prop_map(|s| format!(" // {}\n", s)),
+        "[a-z é😀\n]{0,12}".prop_map(|s| format!(" /* {} */ ", s)),
+    ]
+}
+
+// drops the (unnested) comments the generator produces from a gap between tokens
+fn strip_comments(gap: &str) -> String {
+    let mut out = String::new();
+    let mut rest = gap;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("//") {
+            rest = after.find('\n').map_or("", |nl| &after[nl..]);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.find("*/").map_or("", |end| &after[end + 2..]);
+        } else {
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+// a string with one interpolation, which the properties that scan single
+// tokens on their own can't use: "${" alone doesn't scan
+fn interpolated() -> impl Strategy<Value = String> {
+    r#""[a-z ]{0,3}\$\{ ?[a-z0-9+ ]{1,5} ?\}[a-z ]{0,3}""#
+}
+
+// lexemes separated by whitespace or comments, never glued together
+fn program() -> impl Strategy<Value = String> {
+    prop::collection::vec((lexeme(), trivia()), 0..64).prop_map(|parts| {
+        parts
+            .into_iter()
+            .map(|(lexeme, trivia)| lexeme + &trivia)
+            .collect()
+    })
+}
+
+fn kinds(tokens: &[Token]) -> Vec<TokenType> {
+    tokens.iter().map(|t| t.tt().clone()).collect()
+}
+
+proptest! {
+    #[test]
+    fn spans_are_ordered_and_disjoint(src in program()) {
+        let tokens = scan(&src, &mut Interner::new());
+        for pair in tokens.windows(2) {
+            prop_assert!(pair[0].span().end <= pair[1].span().start);
+        }
+        prop_assert_eq!(tokens.last().unwrap().span().start, src.len());
+    }
+
+    #[test]
+    fn spans_cover_everything_but_trivia(src in program()) {
+        let mut covered_to = 0;
+        for token in scan(&src, &mut Interner::new()) {
+            let span = token.span();
+            let gap = strip_comments(&src[covered_to..span.start]);
+            prop_assert!(gap.trim().is_empty(), "uncovered {:?} before {}", gap, span.start);
+            covered_to = span.end;
+        }
+    }
+
+    #[test]
+    fn each_span_rescans_to_its_token(src in program()) {
+        let mut names = Interner::new();
+        for token in scan(&src, &mut names).iter().filter(|t| *t.tt() != TokenType::End) {
+            let span = token.span();
+            let alone = scan(&src[span.start..span.end], &mut names);
+            prop_assert_eq!(alone.len(), 2); // the token and EOF
+            prop_assert_eq!(alone[0].tt(), token.tt());
+        }
+    }
+
+    #[test]
+    fn printing_and_rescanning_is_idempotent(src in program()) {
+        let mut names = Interner::new();
+        let tokens = scan(&src, &mut names);
+        let printed: Vec<String> = tokens.iter().map(|t| t.lexeme(&names).into_owned()).collect();
+        let rescanned = scan(&printed.join(" "), &mut names);
+        prop_assert_eq!(kinds(&rescanned), kinds(&tokens));
+    }
+
+    #[test]
+    fn stopping_early_matches_a_full_scan(src in program(), take in 0usize..8) {
+        let mut names = Interner::new();
+        let scanner = Scanner::new(FileId::default(), src.clone(), &mut names);
+        let pulled: Vec<Token> = scanner.take(take).map(|t| t.unwrap()).collect();
+        let full = scan(&src, &mut names);
+        prop_assert_eq!(&pulled[..], &full[..take.min(full.len())]);
+    }
+
+    #[test]
+    fn columns_count_chars(src in program()) {
+        for token in scan(&src, &mut Interner::new()) {
+            let span = token.span();
+            let line_start = src[..span.start].rfind('\n').map_or(0, |nl| nl + 1);
+            let chars = src[line_start..span.start].chars().count() as u32;
+            prop_assert_eq!(span.column, chars + 1);
+        }
+    }
+
+    #[test]
+    fn rescanning_an_edit_matches_a_full_scan(
+        src in (program(), interpolated(), program()).prop_map(|(a, b, c)| a + &b + " " + &c),
+        replacement in prop::collection::vec(prop_oneof![lexeme(), trivia(), "[\"`$/*{}]"], 0..3),
+        at in any::<prop::sample::Index>(),
+        len in any::<prop::sample::Index>(),
+    ) {
+        // an edit between char boundaries, at most a few chars long
+        let bounds: Vec<usize> = src.char_indices().map(|(i, _)| i).chain([src.len()]).collect();
+        let start = at.index(bounds.len());
+        let end = (start + len.index(4)).min(bounds.len() - 1);
+        let edit = TextEdit::new(bounds[start]..bounds[end], replacement.concat());
+
+        let mut names = Interner::new();
+        let mut scanner = Scanner::new(FileId::default(), src.clone(), &mut names);
+        let (mut tokens, mut errors) = scanner.scan_tokens();
+        errors.sort_by_key(|err| err.span.start);
+        let mut edited = src.clone();
+        edited.replace_range(edit.range.clone(), &edit.text);
+        scanner.rescan(&tokens, edit).apply(&mut tokens, &mut errors);
+
+        let (expected, mut expected_errors) =
+            Scanner::new(FileId::default(), edited, &mut names).scan_tokens();
+        expected_errors.sort_by_key(|err| err.span.start);
+        prop_assert_eq!(tokens, expected);
+        prop_assert_eq!(errors, expected_errors);
+    }
+}
@@ -0,0 +1,85 @@
+// what --stats reports. the timings vary from run to run, so only the
+// counters are checked exactly
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+use std::{env, fs};
+
+fn stats(flags: &[&str], source: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxrs"))
+        .args(flags)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run loxrs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+// --stats for source run as a script, which unlike a repl program can end
+// in a newline
+fn file_stats(name: &str, source: &str) -> String {
+    let path: PathBuf = env::temp_dir().join(format!("loxrs-{}-{}", process::id(), name));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_loxrs"))
+        .arg("--stats")
+        .arg(&path)
+        .output()
+        .expect("failed to run loxrs");
+    fs::remove_file(&path).unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn counter<'a>(stats: &'a str, name: &str) -> Option<&'a str> {
+    stats
+        .lines()
+        .find_map(|line| line.strip_prefix(name))
+        .map(str::trim)
+}
+
+#[test]
+fn parse_counters() {
+    // the print, the +, and its two operands
+    let out = stats(&["--stats"], "print 1 + 2;\n");
+    assert_eq!(counter(&out, "ast nodes"), Some("4"), "{}", out);
+    assert!(counter(&out, "parse time").is_some(), "{}", out);
+
+    // a class, its method, the method's return, and this
+    let out = stats(&["--stats"], "class A { m() { return this; } }\n");
+    assert_eq!(counter(&out, "ast nodes"), Some("4"), "{}", out);
+
+    // --tokens stops before the parser runs
+    let out = stats(&["--stats", "--tokens"], "print 1;\n");
+    assert_eq!(counter(&out, "tokens scanned"), Some("4"), "{}", out);
+    assert_eq!(counter(&out, "ast nodes"), None, "{}", out);
+}
+
+#[test]
+fn source_lines() {
+    let cases = [
+        ("empty.lox", "", "0"),
+        ("one.lox", "print 1;", "1"),
+        ("one_newline.lox", "print 1;\n", "1"),
+        ("two.lox", "print 1;\nprint 2;", "2"),
+        ("two_newline.lox", "print 1;\nprint 2;\n", "2"),
+        ("blank_last.lox", "print 1;\n\n", "2"),
+    ];
+    for (name, source, lines) in cases {
+        let out = file_stats(name, source);
+        assert_eq!(
+            counter(&out, "source lines"),
+            Some(lines),
+            "{:?}: {}",
+            source,
+            out
+        );
+    }
+}
@@ -0,0 +1,88 @@
+// line-at-a-time lexing for highlighters: each line's tokens, and the state
+// carried from one line into the next
+
+use loxrs::backend::scanner::Scanner;
+use loxrs::data::line_state::{LineState, Open};
+use loxrs::data::symbol::Interner;
+
+// every line's tokens in the compact debug form, plus the final state. the
+// lines share an interner, so a name is the same symbol on all of them
+fn lines(source: &str) -> (Vec<String>, LineState) {
+    let mut state = LineState::default();
+    let mut names = Interner::new();
+    let mut rendered = Vec::new();
+    for line in source.split('\n') {
+        let (tokens, next) = Scanner::tokenize_line(line, &state, &mut names);
+        rendered.push(format!("{:?}", tokens));
+        state = next;
+    }
+    (rendered, state)
+}
+
+#[test]
+fn plain_lines() {
+    let (tokens, state) = lines("var a = 1; // one\nprint a;");
+    assert_eq!(
+        tokens,
+        [
+            r#"[VAR@1:1, IDENTIFIER(#0)@1:5, EQUAL@1:7, NUMBER(1)@1:9, SEMICOLON@1:10, COMMENT("// one")@1:12]"#,
+            "[PRINT@2:1, IDENTIFIER(#0)@2:7, SEMICOLON@2:8]",
+        ]
+    );
+    assert_eq!(
+        state,
+        LineState {
+            line: 3,
+            ..LineState::default()
+        }
+    );
+}
+
+#[test]
+fn tokens_spanning_lines() {
+    let (tokens, state) = lines("a = \"one\ntwo\" + `x\n\ny`; /* /*\n*/ */ b /*");
+    assert_eq!(
+        tokens,
+        [
+            r#"[IDENTIFIER(#0)@1:1, EQUAL@1:3, STRING("one")@1:5]"#,
+            r#"[STRING("two")@2:1, PLUS@2:6, RAW_STRING("x")@2:8]"#,
+            r#"[RAW_STRING("")@3:1]"#,
+            r#"[RAW_STRING("y")@4:1, SEMICOLON@4:3, COMMENT("/* /*")@4:5]"#,
+            r#"[COMMENT("*/ */")@5:1, IDENTIFIER(#1)@5:7, COMMENT("/*")@5:9]"#,
+        ]
+    );
+    assert_eq!(state.open, Some(Open::BlockComment(1)));
+}
+
+#[test]
+fn interpolation_spanning_lines() {
+    let (tokens, state) = lines("\"multi ${\n  f({\n}) } line ${ \"in\nner\" }\"\n");
+    assert_eq!(
+        tokens,
+        [
+            r#"[STRING_PART("multi ")@1:1, INTERP_START@1:8]"#,
+            "[IDENTIFIER(#0)@2:3, LEFT_PAREN@2:4, LEFT_BRACE@2:5]",
+            r#"[RIGHT_BRACE@3:1, RIGHT_PAREN@3:2, INTERP_END@3:4, STRING_PART(" line ")@3:5, INTERP_START@3:11, STRING("in")@3:14]"#,
+            r#"[STRING("ner")@4:1, INTERP_END@4:6, STRING_PART("")@4:7]"#,
+            "[]",
+        ]
+    );
+    assert_eq!(state.interpolations, Vec::<usize>::new());
+    assert_eq!(state.open, None);
+
+    let (_, state) =
+        Scanner::tokenize_line("\"a ${ {", &LineState::default(), &mut Interner::new());
+    assert_eq!(state.interpolations, [1]);
+    let (_, state) =
+        Scanner::tokenize_line("\"a ${ b } c", &LineState::default(), &mut Interner::new());
+    assert_eq!(state.open, Some(Open::String { continued: true }));
+}
+
+#[test]
+fn shebang_only_on_the_first_line() {
+    let (tokens, _) = lines("#!/usr/bin/env loxrs\n#!");
+    assert_eq!(
+        tokens,
+        [r##"[COMMENT("#!/usr/bin/env loxrs")@1:1]"##, "[BANG@2:2]"]
+    );
+}